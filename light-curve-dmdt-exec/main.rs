@@ -1,6 +1,8 @@
 use clap::{Arg, ArgAction, ArgMatches, command, value_parser};
-use enumflags2::{BitFlags, bitflags};
-use light_curve_dmdt::{DmDt, Eps1Over1e3Erf, ExactErf, ndarray, png, to_png};
+use light_curve_dmdt::enumflags2::BitFlags;
+use light_curve_dmdt::{
+    DmDt, DmDtNorm, DmDtNormError, Eps1Over1e3Erf, ExactErf, ndarray, png, to_png,
+};
 use std::fs::File;
 use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::PathBuf;
@@ -13,6 +15,7 @@ enum MainError {
     IoError(#[from] std::io::Error),
     ParseFloatError(#[from] std::num::ParseFloatError),
     PngEncodingError(#[from] png::EncodingError),
+    NormError(#[from] DmDtNormError),
     NotEnoughColumns(&'static str),
 }
 
@@ -46,18 +49,11 @@ fn main() -> Result<(), MainError> {
     let map_u8 = match map_float_or_u8 {
         Array2FloatOrU8::U8(map_u8) => map_u8,
         Array2FloatOrU8::Float(mut map_float) => {
-            if config.norm.contains(DmDtNorm::LgDt) {
-                let lgdt = dmdt.dt_points(&t);
-                let lgdt_no_zeros = lgdt.mapv(|x| if x == 0 { 1.0 } else { x as f32 });
-                map_float /= &lgdt_no_zeros.to_shape((map_float.nrows(), 1)).unwrap();
-            }
-            if config.norm.contains(DmDtNorm::Max) {
-                let max = *map_float
-                    .iter()
-                    .max_by(|&x, &y| x.partial_cmp(y).unwrap())
-                    .unwrap();
-                map_float /= max;
-            }
+            let dt_points = config
+                .norm
+                .contains(DmDtNorm::DtPoints)
+                .then(|| dmdt.dt_points(&t));
+            dmdt.normalise(&mut map_float, config.norm, dt_points.as_deref())?;
             if !config.norm.is_empty() {
                 map_float *= 255.0;
             }
@@ -80,14 +76,6 @@ enum Array2FloatOrU8 {
     U8(ndarray::Array2<u8>),
 }
 
-#[bitflags]
-#[repr(u8)]
-#[derive(Clone, Copy)]
-pub enum DmDtNorm {
-    LgDt,
-    Max,
-}
-
 type Tme2 = (Vec<f32>, Vec<f32>, Option<Vec<f32>>);
 
 fn read_input(input: &Option<PathBuf>, errors: bool) -> Result<Tme2, MainError> {
@@ -300,7 +288,7 @@ impl Config {
             norm: match matches.get_many::<String>("normalisation") {
                 Some(values) => values
                     .map(|s| match s.as_str() {
-                        "lgdt" => DmDtNorm::LgDt,
+                        "lgdt" => DmDtNorm::DtPoints,
                         "max" => DmDtNorm::Max,
                         _ => panic!("the normalisation '{}' is not supported", s),
                     })
@@ -0,0 +1,27 @@
+use criterion::Criterion;
+use light_curve_dmdt::DmDt;
+use ndarray::Array1;
+use std::hint::black_box;
+
+pub fn bench_dt_points(c: &mut Criterion) {
+    let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 3.0_f64, 32, 1.25_f64, 32);
+
+    for n in [101, 1001, 10001] {
+        let t = Array1::linspace(0.0, 1000.0, n);
+        c.bench_function(&format!("dt_points() for {n} points"), |b| {
+            b.iter(|| black_box(dmdt.dt_points(t.as_slice().unwrap())));
+        });
+    }
+
+    // Less points than dt cells
+    let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 3.0_f64, 1024, 1.25_f64, 32);
+    for n in [11, 101] {
+        let t = Array1::linspace(0.0, 1000.0, n);
+        c.bench_function(
+            &format!("dt_points() for {n} points and 1024 dt cells"),
+            |b| {
+                b.iter(|| black_box(dmdt.dt_points(t.as_slice().unwrap())));
+            },
+        );
+    }
+}
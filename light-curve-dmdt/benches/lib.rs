@@ -4,6 +4,9 @@ extern crate criterion;
 mod cond_prob;
 use cond_prob::bench_cond_prob;
 
+mod dt_points;
+use dt_points::bench_dt_points;
+
 mod erf;
 use erf::{bench_erf, bench_erfinv};
 
@@ -14,6 +17,7 @@ mod grid;
 use grid::{bench_linear_grid_idx, bench_log_linear_grids};
 
 criterion_group!(benches_cond_prob, bench_cond_prob);
+criterion_group!(benches_dt_points, bench_dt_points);
 criterion_group!(
     benches_erf,
     bench_erf<f32>,
@@ -31,6 +35,7 @@ criterion_group!(
 
 criterion_main!(
     benches_cond_prob,
+    benches_dt_points,
     benches_erf,
     benches_gausses,
     benches_grid
@@ -0,0 +1,328 @@
+use crate::norm::normalise_by_dt_points;
+use crate::{CellIndex, DmDt, ErfFloat, ErrorFunction, Float, GridTrait};
+
+use ndarray::{Array1, Array2};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+
+/// Incremental dm–dt map builder for streaming observations
+///
+/// It keeps all the observations pushed so far together with [DmDt::points()],
+/// [DmDt::gausses()] and [DmDt::dt_points()] maps of them. Each [DmDtAccumulator::push()] updates
+/// the maps with pairs formed by the new observation only, so it takes O(N) time instead of O(N^2)
+/// needed to rebuild the maps from scratch. Old observations could be removed with
+/// [DmDtAccumulator::remove_before()] or automatically with a sliding time window, see
+/// [DmDtAccumulator::with_window()]. `Erf` is the error function used for the Gaussian smearing,
+/// see [DmDt::gausses()].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DmDtAccumulator<T, Erf>
+where
+    T: Float,
+{
+    dmdt: DmDt<T>,
+    t: Vec<T>,
+    m: Vec<T>,
+    err2: Vec<T>,
+    maps: PairMaps<T>,
+    window: Option<T>,
+    erf: PhantomData<Erf>,
+}
+
+/// Maps of [DmDtAccumulator] updated pair by pair
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct PairMaps<T> {
+    points: Array2<u64>,
+    gausses: Array2<T>,
+    dt_points: Array1<u64>,
+}
+
+impl<T> PairMaps<T>
+where
+    T: ErfFloat,
+{
+    fn add_pair<Erf>(&mut self, dmdt: &DmDt<T>, idx_dt: usize, y1: T, y2: T, d1: T, d2: T)
+    where
+        Erf: ErrorFunction<T>,
+    {
+        self.dt_points[idx_dt] += 1;
+        if let CellIndex::Value(idx_dm) = dmdt.dm_grid.idx(y2 - y1) {
+            self.points[(idx_dt, idx_dm)] += 1;
+        }
+        dmdt.update_gausses_helper::<Erf>(&mut self.gausses, idx_dt, y1, y2, d1, d2);
+    }
+
+    fn subtract_pair<Erf>(&mut self, dmdt: &DmDt<T>, idx_dt: usize, y1: T, y2: T, d1: T, d2: T)
+    where
+        Erf: ErrorFunction<T>,
+    {
+        self.dt_points[idx_dt] -= 1;
+        if let CellIndex::Value(idx_dm) = dmdt.dm_grid.idx(y2 - y1) {
+            self.points[(idx_dt, idx_dm)] -= 1;
+        }
+        dmdt.add_weighted_gauss::<Erf>(
+            &mut self.gausses,
+            idx_dt,
+            y2 - y1,
+            T::sqrt(d1 + d2),
+            -T::one(),
+        );
+    }
+}
+
+impl<T, Erf> DmDtAccumulator<T, Erf>
+where
+    T: ErfFloat,
+    Erf: ErrorFunction<T>,
+{
+    /// Create new empty [DmDtAccumulator]
+    pub fn new(dmdt: DmDt<T>) -> Self {
+        Self {
+            maps: PairMaps {
+                points: Array2::zeros(dmdt.shape()),
+                gausses: Array2::zeros(dmdt.shape()),
+                dt_points: Array1::zeros(dmdt.dt_grid.cell_count()),
+            },
+            dmdt,
+            t: vec![],
+            m: vec![],
+            err2: vec![],
+            window: None,
+            erf: PhantomData,
+        }
+    }
+
+    /// Set sliding time window
+    ///
+    /// After each [DmDtAccumulator::push()] observations older than `window` relative to the
+    /// latest observation are removed, see [DmDtAccumulator::remove_before()].
+    pub fn with_window(mut self, window: T) -> Self {
+        self.window = Some(window);
+        if let Some(&t_max) = self.t.last() {
+            self.remove_before(t_max - window);
+        }
+        self
+    }
+
+    /// Add new observation and update the maps with the pairs it forms
+    ///
+    /// Observations could be pushed in any order, they are kept sorted by time. If the new
+    /// observation has the same time as some of the previous ones, it is considered to be the
+    /// later one.
+    pub fn push(&mut self, t: T, m: T, err2: T) {
+        let idx = self.t.partition_point(|&x| x <= t);
+        self.t.insert(idx, t);
+        self.m.insert(idx, m);
+        self.err2.insert(idx, err2);
+
+        let Self {
+            dmdt,
+            t,
+            m,
+            err2,
+            maps,
+            ..
+        } = self;
+        dmdt.for_each_earlier_pair(t, idx, |idx_dt, i| {
+            maps.add_pair::<Erf>(dmdt, idx_dt, m[i], m[idx], err2[i], err2[idx]);
+        });
+        dmdt.for_each_later_pair(t, idx, |idx_dt, i| {
+            maps.add_pair::<Erf>(dmdt, idx_dt, m[idx], m[i], err2[idx], err2[i]);
+        });
+
+        if let Some(window) = self.window {
+            self.remove_before(self.t[self.t.len() - 1] - window);
+        }
+    }
+
+    /// Remove observations having time less than `t_min` and subtract pairs they form
+    ///
+    /// [DmDtAccumulator::points()] and [DmDtAccumulator::dt_points()] are updated exactly.
+    /// [DmDtAccumulator::gausses()] is updated with floating point subtraction, so rows having no
+    /// pairs left are reset to zero and the values not exceeding the accumulated rounding error
+    /// are zeroed.
+    pub fn remove_before(&mut self, t_min: T) {
+        let n_removed = self.t.partition_point(|&x| x < t_min);
+        if n_removed == 0 {
+            return;
+        }
+        let Self {
+            dmdt,
+            t,
+            m,
+            err2,
+            maps,
+            ..
+        } = self;
+        for i in 0..n_removed {
+            dmdt.for_each_later_pair(t, i, |idx_dt, j| {
+                maps.subtract_pair::<Erf>(dmdt, idx_dt, m[i], m[j], err2[i], err2[j]);
+            });
+        }
+        self.t.drain(..n_removed);
+        self.m.drain(..n_removed);
+        self.err2.drain(..n_removed);
+
+        ndarray::Zip::from(self.maps.gausses.rows_mut())
+            .and(&self.maps.dt_points)
+            .for_each(|mut row, &count| {
+                if count == 0 {
+                    row.fill(T::zero());
+                    return;
+                }
+                let tolerance = T::epsilon() * T::approx_from(count).unwrap();
+                row.mapv_inplace(|x| if x <= tolerance { T::zero() } else { x });
+            });
+    }
+
+    /// Conditional probability `p(m2-m1|t2-t1)` of the observations pushed so far
+    ///
+    /// It is [DmDtAccumulator::gausses()] normalized by [DmDtAccumulator::dt_points()], see
+    /// [DmDt::cond_prob()] for details.
+    pub fn cond_prob(&self) -> Array2<T> {
+        let mut a = self.maps.gausses.clone();
+        normalise_by_dt_points(&mut a, &self.maps.dt_points);
+        a
+    }
+}
+
+impl<T, Erf> DmDtAccumulator<T, Erf>
+where
+    T: Float,
+{
+    /// Grids used to build the maps
+    pub fn dmdt(&self) -> &DmDt<T> {
+        &self.dmdt
+    }
+
+    /// Number of observations pushed so far
+    pub fn len(&self) -> usize {
+        self.t.len()
+    }
+
+    /// Checks if no observations were pushed
+    pub fn is_empty(&self) -> bool {
+        self.t.is_empty()
+    }
+
+    /// Sliding time window, see [DmDtAccumulator::with_window()]
+    pub fn window(&self) -> Option<T> {
+        self.window
+    }
+
+    /// Ascending times of the observations
+    pub fn t(&self) -> &[T] {
+        &self.t
+    }
+
+    /// Magnitudes of the observations, in the same order as [DmDtAccumulator::t()]
+    pub fn m(&self) -> &[T] {
+        &self.m
+    }
+
+    /// Squared magnitude errors of the observations, in the same order as
+    /// [DmDtAccumulator::t()]
+    pub fn err2(&self) -> &[T] {
+        &self.err2
+    }
+
+    /// [DmDt::points()] map of the observations pushed so far
+    pub fn points(&self) -> &Array2<u64> {
+        &self.maps.points
+    }
+
+    /// [DmDt::gausses()] map of the observations pushed so far
+    pub fn gausses(&self) -> &Array2<T> {
+        &self.maps.gausses
+    }
+
+    /// [DmDt::dt_points()] of the observations pushed so far
+    pub fn dt_points(&self) -> &Array1<u64> {
+        &self.maps.dt_points
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::{Eps1Over1e3Erf, ExactErf};
+
+    use approx::assert_abs_diff_eq;
+    use static_assertions::assert_impl_all;
+    use std::fmt::Debug;
+
+    assert_impl_all!(DmDtAccumulator<f32, ExactErf>: Clone, Debug, Send, Sync, Serialize, Deserialize<'static>);
+    assert_impl_all!(DmDtAccumulator<f64, Eps1Over1e3Erf>: Clone, Debug, Send, Sync, Serialize, Deserialize<'static>);
+
+    #[test]
+    fn accumulator_vs_dmdt() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 32, 1.25_f64, 32);
+
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let m = t.mapv(f64::sin);
+        // err is ~0.03
+        let err2 = Array1::from_elem(101, 0.001);
+
+        let mut accumulator = DmDtAccumulator::<_, Eps1Over1e3Erf>::new(dmdt.clone());
+        // Push in some mixed order
+        for i in (0..101).map(|i| (i * 37) % 101) {
+            accumulator.push(t[i], m[i], err2[i]);
+        }
+
+        let t = t.as_slice().unwrap();
+        let m = m.as_slice().unwrap();
+        let err2 = err2.as_slice().unwrap();
+        assert_eq!(accumulator.t(), t);
+        assert_eq!(accumulator.points(), dmdt.points(t, m));
+        assert_eq!(accumulator.dt_points(), dmdt.dt_points(t));
+        assert_abs_diff_eq!(
+            accumulator.gausses().as_slice().unwrap(),
+            dmdt.gausses::<Eps1Over1e3Erf>(t, m, err2)
+                .as_slice()
+                .unwrap(),
+            epsilon = 1e-10,
+        );
+        assert_abs_diff_eq!(
+            accumulator.cond_prob().as_slice().unwrap(),
+            dmdt.cond_prob::<Eps1Over1e3Erf>(t, m, err2)
+                .as_slice()
+                .unwrap(),
+            epsilon = 1e-12,
+        );
+    }
+
+    #[test]
+    fn sliding_window() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 32, 1.25_f64, 32);
+
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let m = t.mapv(f64::sin);
+        // err is ~0.03
+        let err2 = Array1::from_elem(101, 0.001);
+
+        let mut accumulator = DmDtAccumulator::<_, ExactErf>::new(dmdt.clone()).with_window(30.0);
+        for i in 0..101 {
+            accumulator.push(t[i], m[i], err2[i]);
+        }
+
+        let t = &t.as_slice().unwrap()[70..];
+        let m = &m.as_slice().unwrap()[70..];
+        let err2 = &err2.as_slice().unwrap()[70..];
+        assert_eq!(accumulator.t(), t);
+        assert_eq!(accumulator.points(), dmdt.points(t, m));
+        assert_eq!(accumulator.dt_points(), dmdt.dt_points(t));
+        assert_abs_diff_eq!(
+            accumulator.gausses().as_slice().unwrap(),
+            dmdt.gausses::<ExactErf>(t, m, err2).as_slice().unwrap(),
+            epsilon = 1e-10,
+        );
+
+        accumulator.remove_before(f64::INFINITY);
+        assert!(accumulator.is_empty());
+        assert!(accumulator.points().iter().all(|&x| x == 0));
+        assert!(accumulator.gausses().iter().all(|&x| x == 0.0));
+    }
+}
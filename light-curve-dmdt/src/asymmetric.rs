@@ -0,0 +1,227 @@
+use crate::norm::normalise_by_dt_points;
+use crate::{DmDt, ErfFloat, ErrorFunction, GridTrait};
+
+use ndarray::{Array1, Array2, ArrayRef2, s};
+use std::marker::PhantomData;
+
+/// Split-normal (two-piece normal) distribution
+///
+/// It has mode `mode`, the left half is a half of `N(mode, sigma_lower^2)` and the right half is
+/// a half of `N(mode, sigma_upper^2)`, both halves are scaled to make the density continuous.
+#[derive(Clone, Copy, Debug)]
+struct SplitNormal<T, Erf> {
+    mode: T,
+    sigma_lower: T,
+    sigma_upper: T,
+    erf: PhantomData<Erf>,
+}
+
+impl<T, Erf> SplitNormal<T, Erf>
+where
+    T: ErfFloat,
+    Erf: ErrorFunction<T>,
+{
+    fn cdf(&self, x: T) -> T {
+        let sigma_sum = self.sigma_lower + self.sigma_upper;
+        if x < self.mode {
+            (self.sigma_lower + self.sigma_lower) / sigma_sum
+                * Erf::normal_cdf(x, self.mode, self.sigma_lower)
+        } else {
+            (self.sigma_lower
+                + (self.sigma_upper + self.sigma_upper)
+                    * (Erf::normal_cdf(x, self.mode, self.sigma_upper) - T::half()))
+                / sigma_sum
+        }
+    }
+
+    fn min_x_nonzero_cdf(&self) -> T {
+        self.mode + Erf::min_dx_nonzero_normal_cdf(self.sigma_lower)
+    }
+
+    fn max_x_nonunity_cdf(&self) -> T {
+        self.mode + Erf::max_dx_nonunity_normal_cdf(self.sigma_upper)
+    }
+}
+
+impl<T> DmDt<T>
+where
+    T: ErfFloat,
+{
+    /// Adds split-normal distribution multiplied by `weight` to the `idx_dt` row of the given map
+    fn add_weighted_split_normal<Erf>(
+        &self,
+        a: &mut ArrayRef2<T>,
+        idx_dt: usize,
+        distr: SplitNormal<T, Erf>,
+        weight: T,
+    ) where
+        Erf: ErrorFunction<T>,
+    {
+        let Some((min_idx_dm, values)) = Self::cdf_cell_integrals(
+            &self.dm_grid,
+            distr.min_x_nonzero_cdf(),
+            distr.max_x_nonunity_cdf(),
+            move |x| distr.cdf(x),
+        ) else {
+            return;
+        };
+        a.slice_mut(s![idx_dt, min_idx_dm..])
+            .iter_mut()
+            .zip(values)
+            .for_each(|(cell, value)| *cell += weight * value);
+    }
+
+    /// Calls `f(idx_dt, distr)` for each pair of points with split-normal dm distribution
+    fn for_each_split_normal_pair<Erf, F>(
+        &self,
+        t: &[T],
+        m: &[T],
+        err2_lower: &[T],
+        err2_upper: &[T],
+        mut f: F,
+    ) where
+        Erf: ErrorFunction<T>,
+        F: FnMut(usize, SplitNormal<T, Erf>),
+    {
+        self.for_each_pair(t, |idx_dt, i1, i2| {
+            // dm is lower than m2 - m1 if m2 is lower or m1 is upper
+            let distr = SplitNormal {
+                mode: m[i2] - m[i1],
+                sigma_lower: T::sqrt(err2_lower[i2] + err2_upper[i1]),
+                sigma_upper: T::sqrt(err2_upper[i2] + err2_lower[i1]),
+                erf: PhantomData,
+            };
+            f(idx_dt, distr);
+        });
+    }
+
+    /// Represents each pair of points having asymmetric errors as a split-normal distribution in
+    /// dm-dt map
+    ///
+    /// `t` must be an ascending slice. `err2_lower` and `err2_upper` are squared errors towards
+    /// smaller and larger magnitudes respectively, each observation is assumed to have
+    /// split-normal distribution of its magnitude. The difference of two split-normal values is
+    /// approximated by split-normal distribution having mode `m2 - m1`, the lower half sigma
+    /// `sqrt(err2_lower_2 + err2_upper_1)` and the upper half sigma
+    /// `sqrt(err2_upper_2 + err2_lower_1)`. It is the same as [DmDt::gausses()] for symmetric
+    /// errors.
+    pub fn asymmetric_gausses<Erf>(
+        &self,
+        t: &[T],
+        m: &[T],
+        err2_lower: &[T],
+        err2_upper: &[T],
+    ) -> Array2<T>
+    where
+        Erf: ErrorFunction<T>,
+    {
+        let mut a = Array2::zeros(self.shape());
+        self.for_each_split_normal_pair::<Erf, _>(t, m, err2_lower, err2_upper, |idx_dt, distr| {
+            self.add_weighted_split_normal(&mut a, idx_dt, distr, T::one());
+        });
+        a
+    }
+
+    /// Conditional probability `p(m2-m1|t2-t1)` for points having asymmetric errors
+    ///
+    /// It is [DmDt::asymmetric_gausses()] normalized by [DmDt::dt_points()].
+    pub fn asymmetric_cond_prob<Erf>(
+        &self,
+        t: &[T],
+        m: &[T],
+        err2_lower: &[T],
+        err2_upper: &[T],
+    ) -> Array2<T>
+    where
+        Erf: ErrorFunction<T>,
+    {
+        let mut a = Array2::zeros(self.shape());
+        let mut dt_points: Array1<u64> = Array1::zeros(self.dt_grid.cell_count());
+        self.for_each_split_normal_pair::<Erf, _>(t, m, err2_lower, err2_upper, |idx_dt, distr| {
+            dt_points[idx_dt] += 1;
+            self.add_weighted_split_normal(&mut a, idx_dt, distr, T::one());
+        });
+        normalise_by_dt_points(&mut a, &dt_points);
+        a
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::ExactErf;
+
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn split_normal_cdf() {
+        let distr = SplitNormal::<f64, ExactErf> {
+            mode: 1.0,
+            sigma_lower: 0.5,
+            sigma_upper: 2.0,
+            erf: PhantomData,
+        };
+        assert_abs_diff_eq!(distr.cdf(1.0), 0.2, epsilon = 1e-15);
+        assert_abs_diff_eq!(distr.cdf(distr.min_x_nonzero_cdf()), 0.0, epsilon = 1e-15);
+        assert_abs_diff_eq!(distr.cdf(distr.max_x_nonunity_cdf()), 1.0, epsilon = 1e-15);
+        // One sigma from the mode
+        let phi = ExactErf::normal_cdf(1.0, 0.0, 1.0);
+        assert_abs_diff_eq!(distr.cdf(0.5), 0.4 * (1.0 - phi), epsilon = 1e-15);
+        assert_abs_diff_eq!(
+            distr.cdf(3.0),
+            0.2 + 0.8 * 2.0 * (phi - 0.5),
+            epsilon = 1e-15
+        );
+    }
+
+    #[test]
+    fn symmetric_errors() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 32, 1.25_f64, 32);
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let m = t.mapv(f64::sin);
+        let err2 = Array1::from_elem(101, 0.001);
+        let (t, m, err2) = (
+            t.as_slice().unwrap(),
+            m.as_slice().unwrap(),
+            err2.as_slice().unwrap(),
+        );
+
+        assert_abs_diff_eq!(
+            dmdt.asymmetric_gausses::<ExactErf>(t, m, err2, err2)
+                .as_slice()
+                .unwrap(),
+            dmdt.gausses::<ExactErf>(t, m, err2).as_slice().unwrap(),
+            epsilon = 1e-12,
+        );
+        assert_abs_diff_eq!(
+            dmdt.asymmetric_cond_prob::<ExactErf>(t, m, err2, err2)
+                .as_slice()
+                .unwrap(),
+            dmdt.cond_prob::<ExactErf>(t, m, err2).as_slice().unwrap(),
+            epsilon = 1e-12,
+        );
+    }
+
+    #[test]
+    fn skewness() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 2, 2.0_f64, 40);
+        let t = [0.0, 30.0];
+        let m = [1.0, 1.0];
+        let err2_lower = [0.01, 0.01];
+        let err2_upper = [0.04, 0.04];
+
+        let map = dmdt.asymmetric_cond_prob::<ExactErf>(&t, &m, &err2_lower, &err2_upper);
+        let row = map.row(1);
+        assert_abs_diff_eq!(row.sum(), 1.0, epsilon = 1e-12);
+        // dm = 0 is the mode, both halves have the same sigma, so the distribution is symmetric
+        for i in 0..20 {
+            assert_abs_diff_eq!(row[i], row[39 - i], epsilon = 1e-12);
+        }
+
+        let map = dmdt.asymmetric_cond_prob::<ExactErf>(&t, &m, &err2_lower, &[0.01, 0.04]);
+        let row = map.row(1);
+        // Larger upper error of the second point makes larger dm more probable
+        assert!(row.slice(s![20..]).sum() > row.slice(s![..20]).sum());
+    }
+}
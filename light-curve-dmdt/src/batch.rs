@@ -0,0 +1,125 @@
+use crate::{DmDt, ErfFloat, ErrorFunction, Float};
+
+use ndarray::{Array3, ArrayViewMut2, Zip};
+
+impl<T> DmDt<T>
+where
+    T: Float,
+{
+    fn zeros_many<V>(&self, n: usize) -> Array3<V>
+    where
+        V: Clone + num_traits::Zero,
+    {
+        let (n_dt, n_dm) = self.shape();
+        Array3::zeros((n, n_dt, n_dm))
+    }
+
+    /// [DmDt::points] for many light curves
+    ///
+    /// Each light curve is a `(t, m)` pair, `t` must be an ascending slice. Output shape is
+    /// (number of light curves, N dt, N dm). Light curves are processed in parallel if `rayon`
+    /// Cargo feature is enabled.
+    pub fn points_many(&self, lcs: &[(&[T], &[T])]) -> Array3<u64> {
+        let mut a = self.zeros_many(lcs.len());
+        let zip = Zip::from(a.outer_iter_mut()).and(lcs);
+        let f =
+            |mut map: ArrayViewMut2<u64>, &(t, m): &(&[T], &[T])| self.add_points(&mut map, t, m);
+        #[cfg(feature = "rayon")]
+        zip.par_for_each(f);
+        #[cfg(not(feature = "rayon"))]
+        zip.for_each(f);
+        a
+    }
+
+    /// [DmDt::gausses] for many light curves
+    ///
+    /// Each light curve is a `(t, m, err2)` triple, `t` must be an ascending slice. Output shape is
+    /// (number of light curves, N dt, N dm). Light curves are processed in parallel if `rayon`
+    /// Cargo feature is enabled.
+    pub fn gausses_many<Erf>(&self, lcs: &[(&[T], &[T], &[T])]) -> Array3<T>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        let mut a = self.zeros_many(lcs.len());
+        let zip = Zip::from(a.outer_iter_mut()).and(lcs);
+        let f = |mut map: ArrayViewMut2<T>, &(t, m, err2): &(&[T], &[T], &[T])| {
+            self.add_gausses::<Erf>(&mut map, t, m, err2)
+        };
+        #[cfg(feature = "rayon")]
+        zip.par_for_each(f);
+        #[cfg(not(feature = "rayon"))]
+        zip.for_each(f);
+        a
+    }
+
+    /// [DmDt::cond_prob] for many light curves
+    ///
+    /// Each light curve is a `(t, m, err2)` triple, `t` must be an ascending slice. Output shape is
+    /// (number of light curves, N dt, N dm). Light curves are processed in parallel if `rayon`
+    /// Cargo feature is enabled.
+    pub fn cond_prob_many<Erf>(&self, lcs: &[(&[T], &[T], &[T])]) -> Array3<T>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        let mut a = self.zeros_many(lcs.len());
+        let zip = Zip::from(a.outer_iter_mut()).and(lcs);
+        let f = |mut map: ArrayViewMut2<T>, &(t, m, err2): &(&[T], &[T], &[T])| {
+            self.fill_cond_prob::<Erf>(&mut map, t, m, err2)
+        };
+        #[cfg(feature = "rayon")]
+        zip.par_for_each(f);
+        #[cfg(not(feature = "rayon"))]
+        zip.for_each(f);
+        a
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::Eps1Over1e3Erf;
+
+    use ndarray::{Array1, Axis};
+
+    #[test]
+    fn many_vs_single() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f32, 2.0_f32, 32, 1.25_f32, 32);
+
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let m1 = t.mapv(f32::sin);
+        let m2 = t.mapv(f32::cos);
+        // err is ~0.03
+        let err2 = Array1::from_elem(101, 0.001_f32);
+        let lcs = [
+            (
+                t.as_slice().unwrap(),
+                m1.as_slice().unwrap(),
+                err2.as_slice().unwrap(),
+            ),
+            (
+                &t.as_slice().unwrap()[..50],
+                &m2.as_slice().unwrap()[..50],
+                &err2.as_slice().unwrap()[..50],
+            ),
+        ];
+
+        let points = dmdt.points_many(&lcs.map(|(t, m, _err2)| (t, m)));
+        let gausses = dmdt.gausses_many::<Eps1Over1e3Erf>(&lcs);
+        let cond_prob = dmdt.cond_prob_many::<Eps1Over1e3Erf>(&lcs);
+
+        for (i, &(t, m, err2)) in lcs.iter().enumerate() {
+            assert_eq!(points.index_axis(Axis(0), i), dmdt.points(t, m));
+            assert_eq!(
+                gausses.index_axis(Axis(0), i),
+                dmdt.gausses::<Eps1Over1e3Erf>(t, m, err2)
+            );
+            assert_eq!(
+                cond_prob.index_axis(Axis(0), i),
+                dmdt.cond_prob::<Eps1Over1e3Erf>(t, m, err2)
+            );
+        }
+    }
+}
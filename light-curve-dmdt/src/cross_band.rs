@@ -0,0 +1,159 @@
+use crate::{CellIndex, DmDt, ErfFloat, ErrorFunction, Float, GridTrait};
+
+use ndarray::Array2;
+
+/// Which pairs of observations of two passbands to use for cross-band maps
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CrossPairs {
+    /// Pair each observation in passband A with each observation in passband B having the same
+    /// or a later time moment
+    Later,
+    /// Pair each observation in passband A with each observation in passband B regardless of the
+    /// time order, dt is `|t_B - t_A|`
+    Any,
+}
+
+impl<T> DmDt<T>
+where
+    T: Float,
+{
+    /// Calls `f(idx_dt, i_a, i_b)` for every pair of observations of two series having dt inside
+    /// the dt grid
+    ///
+    /// Both `t_a` and `t_b` must be ascending slices
+    pub(crate) fn for_each_cross_pair<F>(&self, t_a: &[T], t_b: &[T], pairs: CrossPairs, mut f: F)
+    where
+        F: FnMut(usize, usize, usize),
+    {
+        for (i_a, &x_a) in t_a.iter().enumerate() {
+            let first_later = t_b.partition_point(|&x_b| x_b < x_a);
+            for (i_b, &x_b) in t_b.iter().enumerate().skip(first_later) {
+                let idx_dt = match self.dt_grid.idx(x_b - x_a) {
+                    CellIndex::LowerMin => continue,
+                    CellIndex::GreaterMax => break,
+                    CellIndex::Value(idx_dt) => idx_dt,
+                };
+                f(idx_dt, i_a, i_b);
+            }
+            if pairs == CrossPairs::Any {
+                for (i_b, &x_b) in t_b[..first_later].iter().enumerate().rev() {
+                    let idx_dt = match self.dt_grid.idx(x_a - x_b) {
+                        CellIndex::LowerMin => continue,
+                        CellIndex::GreaterMax => break,
+                        CellIndex::Value(idx_dt) => idx_dt,
+                    };
+                    f(idx_dt, i_a, i_b);
+                }
+            }
+        }
+    }
+
+    /// Represents each cross-band pair of (t, m) points as a unity value in dm-dt map
+    ///
+    /// `a` and `b` are `(t, m)` pairs of passbands A and B, both `t` must be ascending slices.
+    /// Each pair of observations is placed to the cell of `dt = |t_B - t_A|` and
+    /// `dm = m_B - m_A`, so the map represents colour variability. `pairs` specifies which pairs
+    /// of observations are used.
+    pub fn cross_points(&self, a: (&[T], &[T]), b: (&[T], &[T]), pairs: CrossPairs) -> Array2<u64> {
+        let (t_a, m_a) = a;
+        let (t_b, m_b) = b;
+        let mut map = Array2::zeros(self.shape());
+        self.for_each_cross_pair(t_a, t_b, pairs, |idx_dt, i_a, i_b| {
+            if let CellIndex::Value(idx_dm) = self.dm_grid.idx(m_b[i_b] - m_a[i_a]) {
+                map[(idx_dt, idx_dm)] += 1;
+            }
+        });
+        map
+    }
+
+    /// Represents each cross-band pair of (t, m, err2) points as a Gaussian distribution in dm-dt
+    /// map
+    ///
+    /// `a` and `b` are `(t, m, err2)` triples of passbands A and B, both `t` must be ascending
+    /// slices. Each pair of observations is represented by 1-D Gaussian having constant
+    /// `dt = |t_B - t_A|` and `dm ~ N(m_B - m_A, err2_A + err2_B)`, see [DmDt::gausses()] for
+    /// details. `pairs` specifies which pairs of observations are used.
+    pub fn cross_gausses<Erf>(
+        &self,
+        a: (&[T], &[T], &[T]),
+        b: (&[T], &[T], &[T]),
+        pairs: CrossPairs,
+    ) -> Array2<T>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        let (t_a, m_a, err2_a) = a;
+        let (t_b, m_b, err2_b) = b;
+        let mut map = Array2::zeros(self.shape());
+        self.for_each_cross_pair(t_a, t_b, pairs, |idx_dt, i_a, i_b| {
+            self.update_gausses_helper::<Erf>(
+                &mut map,
+                idx_dt,
+                m_a[i_a],
+                m_b[i_b],
+                err2_a[i_a],
+                err2_b[i_b],
+            );
+        });
+        map
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::ExactErf;
+
+    use approx::assert_abs_diff_eq;
+    use ndarray::{Array1, Axis};
+
+    #[test]
+    fn cross_points_same_band() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f32, 2.0_f32, 32, 3.0_f32, 32);
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let m = t.mapv(f32::sin);
+        let lc = (t.as_slice().unwrap(), m.as_slice().unwrap());
+
+        assert_eq!(
+            dmdt.cross_points(lc, lc, CrossPairs::Later),
+            dmdt.points(lc.0, lc.1)
+        );
+    }
+
+    #[test]
+    fn cross_points_vs_gausses() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f32, 2.0_f32, 32, 3.0_f32, 32);
+        let t_a = Array1::linspace(0.0, 100.0, 101);
+        let m_a = t_a.mapv(f32::sin);
+        let err2_a = Array1::from_elem(101, 0.001_f32);
+        let t_b = Array1::linspace(0.5, 90.5, 31);
+        let m_b = t_b.mapv(|x| f32::cos(x) + 0.5);
+        let err2_b = Array1::from_elem(31, 0.002_f32);
+        let a = (
+            t_a.as_slice().unwrap(),
+            m_a.as_slice().unwrap(),
+            err2_a.as_slice().unwrap(),
+        );
+        let b = (
+            t_b.as_slice().unwrap(),
+            m_b.as_slice().unwrap(),
+            err2_b.as_slice().unwrap(),
+        );
+
+        for pairs in [CrossPairs::Later, CrossPairs::Any] {
+            let points = dmdt.cross_points((a.0, a.1), (b.0, b.1), pairs);
+            let gausses = dmdt.cross_gausses::<ExactErf>(a, b, pairs);
+            assert_abs_diff_eq!(
+                gausses.sum_axis(Axis(1)).as_slice().unwrap(),
+                points
+                    .sum_axis(Axis(1))
+                    .mapv(|x| x as f32)
+                    .as_slice()
+                    .unwrap(),
+                epsilon = 1e-4,
+            );
+        }
+    }
+}
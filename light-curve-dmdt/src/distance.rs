@@ -0,0 +1,274 @@
+use crate::{DmDt, DmDtCompatibilityError, DmDtShapeError, Float, GridTrait};
+
+use ndarray::{Array1, ArrayRef2, ArrayView1, Axis, Zip};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Distance or divergence between two dm–dt maps, see [DmDt::distance()]
+///
+/// All metrics are computed for each dt row independently, the row is normalised to unity sum
+/// first. Rows having zero sum are treated as uniform distributions over dm cells.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DmDtDistance<T> {
+    /// Kullback–Leibler divergence `KL(a || b)` in nats
+    ///
+    /// `smoothing` is added to every cell of both rows before normalisation, so zero cells of
+    /// `b` don't give infinite divergence.
+    KullbackLeibler { smoothing: T },
+    /// Jensen–Shannon divergence in nats, it is symmetric and bounded by `ln 2`
+    ///
+    /// `smoothing` is added to every cell of both rows before normalisation.
+    JensenShannon { smoothing: T },
+    /// Hellinger distance `sqrt(1 - sum sqrt(a b))`, it is bounded by unity
+    Hellinger,
+    /// Earth mover's distance (1-Wasserstein distance) along dm axis, in dm units
+    ///
+    /// Mass of a cell is assumed to be at the cell centre.
+    EarthMover,
+}
+
+/// Error type for [DmDt::distance()] and [DmDt::distance_per_dt()]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DmDtDistanceError {
+    #[error("incompatible grids: {0}")]
+    Incompatible(#[from] DmDtCompatibilityError),
+    #[error("shape error: {0}")]
+    Shape(#[from] DmDtShapeError),
+}
+
+/// Normalise row to unity sum after adding `smoothing` to every cell
+fn normalised_row<T: Float>(row: ArrayView1<T>, smoothing: T) -> Array1<T> {
+    let row = row.mapv(|x| x + smoothing);
+    let sum = row.sum();
+    if sum.is_zero() {
+        let n = T::value_from(row.len()).unwrap();
+        return Array1::from_elem(row.len(), n.recip());
+    }
+    row / sum
+}
+
+fn kullback_leibler<T: Float>(p: &Array1<T>, q: &Array1<T>) -> T {
+    Zip::from(p).and(q).fold(T::zero(), |acc, &p, &q| {
+        if p.is_zero() {
+            acc
+        } else {
+            acc + p * T::ln(p / q)
+        }
+    })
+}
+
+impl<T> DmDt<T>
+where
+    T: Float,
+{
+    /// Distance between maps `a` of this object and `b` of `other` object for each dt row
+    ///
+    /// Maps are usually outputs of [DmDt::cond_prob()], but any non-negative map is accepted.
+    /// Grids are checked by [DmDt::check_compatible()] first.
+    pub fn distance_per_dt(
+        &self,
+        a: &ArrayRef2<T>,
+        other: &Self,
+        b: &ArrayRef2<T>,
+        metric: DmDtDistance<T>,
+    ) -> Result<Array1<T>, DmDtDistanceError> {
+        self.check_compatible(other)?;
+        self.check_map_shape(a)?;
+        self.check_map_shape(b)?;
+
+        let smoothing = match metric {
+            DmDtDistance::KullbackLeibler { smoothing }
+            | DmDtDistance::JensenShannon { smoothing } => smoothing,
+            DmDtDistance::Hellinger | DmDtDistance::EarthMover => T::zero(),
+        };
+        let dm_borders = self.dm_grid.get_borders();
+        let distances = a
+            .axis_iter(Axis(0))
+            .zip(b.axis_iter(Axis(0)))
+            .map(|(a, b)| {
+                let p = normalised_row(a, smoothing);
+                let q = normalised_row(b, smoothing);
+                match metric {
+                    DmDtDistance::KullbackLeibler { .. } => kullback_leibler(&p, &q),
+                    DmDtDistance::JensenShannon { .. } => {
+                        let mixture = (&p + &q) * T::half();
+                        T::half()
+                            * (kullback_leibler(&p, &mixture) + kullback_leibler(&q, &mixture))
+                    }
+                    DmDtDistance::Hellinger => {
+                        let bhattacharyya = Zip::from(&p)
+                            .and(&q)
+                            .fold(T::zero(), |acc, &p, &q| acc + T::sqrt(p * q));
+                        // Round-off could make the coefficient slightly larger than unity
+                        T::sqrt(T::max(T::one() - bhattacharyya, T::zero()))
+                    }
+                    DmDtDistance::EarthMover => {
+                        // Integral of |CDF_p - CDF_q| with mass being at cell centres, so
+                        // CDFs are constant between centres of adjacent cells
+                        let mut cdf_diff = T::zero();
+                        let mut distance = T::zero();
+                        for ((&p, &q), step) in p.iter().zip(q.iter()).zip(
+                            dm_borders
+                                .windows(3)
+                                .into_iter()
+                                .map(|w| T::half() * (w[2] - w[0])),
+                        ) {
+                            cdf_diff += p - q;
+                            distance += cdf_diff.abs() * step;
+                        }
+                        distance
+                    }
+                }
+            })
+            .collect();
+        Ok(distances)
+    }
+
+    /// Aggregate distance between maps `a` of this object and `b` of `other` object
+    ///
+    /// It is the mean of [DmDt::distance_per_dt()] over dt rows.
+    pub fn distance(
+        &self,
+        a: &ArrayRef2<T>,
+        other: &Self,
+        b: &ArrayRef2<T>,
+        metric: DmDtDistance<T>,
+    ) -> Result<T, DmDtDistanceError> {
+        let distances = self.distance_per_dt(a, other, b, metric)?;
+        Ok(distances.sum() / T::value_from(distances.len()).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::Grid;
+    use crate::erf::ExactErf;
+
+    use approx::assert_abs_diff_eq;
+    use ndarray::{Array2, array};
+
+    #[test]
+    fn identical_maps() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 16, 1.25_f64, 16);
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let m = t.mapv(f64::sin);
+        let err2 = Array1::from_elem(101, 0.01);
+        let map = dmdt.cond_prob::<ExactErf>(
+            t.as_slice().unwrap(),
+            m.as_slice().unwrap(),
+            err2.as_slice().unwrap(),
+        );
+        for metric in [
+            DmDtDistance::KullbackLeibler { smoothing: 1e-6 },
+            DmDtDistance::JensenShannon { smoothing: 1e-6 },
+            DmDtDistance::Hellinger,
+            DmDtDistance::EarthMover,
+        ] {
+            assert_abs_diff_eq!(
+                dmdt.distance(&map, &dmdt, &map, metric).unwrap(),
+                0.0,
+                epsilon = 1e-7
+            );
+        }
+    }
+
+    #[test]
+    fn disjoint_rows() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 1.0_f64, 2, 2.0_f64, 4);
+        let a: Array2<f64> = array![[1.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0]];
+        let b: Array2<f64> = array![[0.0, 0.0, 0.0, 2.0], [0.0, 0.0, 0.0, 0.0]];
+
+        let js = dmdt
+            .distance_per_dt(
+                &a,
+                &dmdt,
+                &b,
+                DmDtDistance::JensenShannon { smoothing: 0.0 },
+            )
+            .unwrap();
+        assert_abs_diff_eq!(
+            js.as_slice().unwrap(),
+            &[f64::ln(2.0), 0.0][..],
+            epsilon = 1e-12
+        );
+
+        let hellinger = dmdt
+            .distance_per_dt(&a, &dmdt, &b, DmDtDistance::Hellinger)
+            .unwrap();
+        assert_abs_diff_eq!(
+            hellinger.as_slice().unwrap(),
+            &[1.0, 0.0][..],
+            epsilon = 1e-12
+        );
+
+        // All mass moves by three cells of unity width
+        let emd = dmdt
+            .distance_per_dt(&a, &dmdt, &b, DmDtDistance::EarthMover)
+            .unwrap();
+        assert_abs_diff_eq!(emd.as_slice().unwrap(), &[3.0, 0.0][..], epsilon = 1e-12);
+        assert_abs_diff_eq!(
+            dmdt.distance(&a, &dmdt, &b, DmDtDistance::EarthMover)
+                .unwrap(),
+            1.5,
+            epsilon = 1e-12
+        );
+
+        let kl = dmdt
+            .distance_per_dt(
+                &a,
+                &dmdt,
+                &b,
+                DmDtDistance::KullbackLeibler { smoothing: 1e-3 },
+            )
+            .unwrap();
+        assert!(kl[0].is_finite() && kl[0] > 1.0);
+    }
+
+    #[test]
+    fn earth_mover_non_uniform() {
+        let a: Array2<f64> = array![[1.0, 0.0]];
+        let b: Array2<f64> = array![[0.0, 1.0]];
+        // Mass moves from the centre of the first cell to the centre of the second one
+        for dm_borders in [array![0.0, 1.0, 11.0], array![0.0, 10.0, 11.0]] {
+            let dmdt = DmDt::from_grids(
+                Grid::linear(1.0_f64, 10.0, 1),
+                Grid::array(dm_borders).unwrap(),
+            );
+            assert_abs_diff_eq!(
+                dmdt.distance(&a, &dmdt, &b, DmDtDistance::EarthMover)
+                    .unwrap(),
+                5.5,
+                epsilon = 1e-12
+            );
+        }
+    }
+
+    #[test]
+    fn incompatible() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 16, 1.25_f64, 16);
+        let map = Array2::zeros(dmdt.shape());
+        let other_dt = DmDt::from_lgdt_dm_limits(0.0_f64, 3.0_f64, 16, 1.25_f64, 16);
+        let other_dm = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 16, 1.5_f64, 16);
+        assert_eq!(
+            dmdt.distance(&map, &other_dt, &map, DmDtDistance::Hellinger),
+            Err(DmDtCompatibilityError::DtGridMismatch.into())
+        );
+        assert_eq!(
+            dmdt.distance(&map, &other_dm, &map, DmDtDistance::Hellinger),
+            Err(DmDtCompatibilityError::DmGridMismatch.into())
+        );
+        assert_eq!(
+            dmdt.distance(
+                &map,
+                &dmdt,
+                &Array2::zeros((16, 8)),
+                DmDtDistance::Hellinger
+            ),
+            Err(DmDtShapeError::MapShapeMismatch((16, 8), (16, 16)).into())
+        );
+    }
+}
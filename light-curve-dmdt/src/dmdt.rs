@@ -1,13 +1,15 @@
+use crate::norm::normalise_by_dt_points;
 use crate::{
-    ArrayGridError, BayesianBlocksError, CellIndex, ErfFloat, ErrorFunction, Float, Grid,
-    GridTrait, LgGrid, LinearGrid, bayesian_blocks,
+    ArrayGridError, BayesianBlocksError, CellIndex, ErfFloat, ErrorFunction, Float, GaussianKernel,
+    Grid, GridTrait, LgGrid, LinearGrid, SmearingKernel, bayesian_blocks,
 };
 
 use itertools::Itertools;
-use ndarray::{Array1, Array2, s};
+use ndarray::{Array1, Array2, ArrayRef1, ArrayRef2, s};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
+use std::ops::Range;
 use thiserror::Error;
 
 /// Error type for automatic grid creation
@@ -23,7 +25,40 @@ pub enum DmDtAutoGridError {
     GridError(#[from] ArrayGridError),
 }
 
+/// Error type for shape checks of maps and `dt_points` arrays passed to [DmDt] methods
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DmDtShapeError {
+    #[error("map shape {0:?} doesn't match DmDt shape {1:?}")]
+    MapShapeMismatch((usize, usize), (usize, usize)),
+    #[error("dt_points length {0} doesn't match dt grid cell count {1}")]
+    DtPointsShapeMismatch(usize, usize),
+}
+
+/// Kind of dm–dt map, i.e. the [DmDt] method producing it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DmDtMapKind {
+    /// [DmDt::points()]
+    Points,
+    /// [DmDt::gausses()]
+    Gausses,
+    /// [DmDt::cond_prob()]
+    CondProb,
+}
+
+/// Error type for [DmDt::check_compatible()]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DmDtCompatibilityError {
+    #[error("dt grids are not the same")]
+    DtGridMismatch,
+    #[error("dm grids are not the same")]
+    DmGridMismatch,
+}
+
 /// dm–dt map plotter
+///
+/// With `serde` feature it is serialisable unless any of its grids uses
+/// [Transform::Custom](crate::Transform::Custom), serialising such an object returns an error.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DmDt<T>
@@ -142,7 +177,7 @@ where
     }
 
     /// Compute all pairwise time differences, sorted in ascending order
-    fn sorted_dt(t: &[T]) -> Vec<T> {
+    pub(crate) fn sorted_dt(t: &[T]) -> Vec<T> {
         let n = t.len();
         if n < 2 {
             return Vec::new();
@@ -157,7 +192,7 @@ where
     }
 
     /// Compute all pairwise magnitude differences, sorted in ascending order
-    fn sorted_dm(m: &[T]) -> Vec<T> {
+    pub(crate) fn sorted_dm(m: &[T]) -> Vec<T> {
         let n = m.len();
         if n < 2 {
             return Vec::new();
@@ -176,33 +211,100 @@ where
         (self.dt_grid.cell_count(), self.dm_grid.cell_count())
     }
 
+    /// Checks if two [DmDt] objects have the same grids, so their maps can be compared or merged
+    pub fn check_compatible(&self, other: &Self) -> Result<(), DmDtCompatibilityError> {
+        if self.dt_grid.get_borders() != other.dt_grid.get_borders() {
+            return Err(DmDtCompatibilityError::DtGridMismatch);
+        }
+        if self.dm_grid.get_borders() != other.dm_grid.get_borders() {
+            return Err(DmDtCompatibilityError::DmGridMismatch);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_map_shape<V>(&self, map: &ArrayRef2<V>) -> Result<(), DmDtShapeError> {
+        if map.dim() != self.shape() {
+            return Err(DmDtShapeError::MapShapeMismatch(map.dim(), self.shape()));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_dt_points_shape(
+        &self,
+        dt_points: &ArrayRef1<u64>,
+    ) -> Result<(), DmDtShapeError> {
+        let n_dt = self.dt_grid.cell_count();
+        if dt_points.len() != n_dt {
+            return Err(DmDtShapeError::DtPointsShapeMismatch(dt_points.len(), n_dt));
+        }
+        Ok(())
+    }
+
     /// Represents each pair of (t, m) points as a unity value in dm-dt map
     ///
     /// `t` must be an ascending slice
     pub fn points(&self, t: &[T], m: &[T]) -> Array2<u64> {
         let mut a = Array2::zeros(self.shape());
-        for (i1, (&x1, &y1)) in t.iter().zip(m.iter()).enumerate() {
-            for (&x2, &y2) in t[i1 + 1..].iter().zip(m[i1 + 1..].iter()) {
-                let dt = x2 - x1;
-                let idx_dt = match self.dt_grid.idx(dt) {
-                    CellIndex::LowerMin => continue,
-                    CellIndex::GreaterMax => break,
-                    CellIndex::Value(idx_dt) => idx_dt,
-                };
-                let dm = y2 - y1;
-                let idx_dm = match self.dm_grid.idx(dm) {
-                    CellIndex::Value(idx_dm) => idx_dm,
-                    CellIndex::LowerMin | CellIndex::GreaterMax => continue,
-                };
+        self.add_points(&mut a, t, m);
+        a
+    }
+
+    /// Adds unity value of each pair of (t, m) points to the given map
+    pub(crate) fn add_points(&self, a: &mut ArrayRef2<u64>, t: &[T], m: &[T]) {
+        self.for_each_pair(t, |idx_dt, i1, i2| {
+            if let CellIndex::Value(idx_dm) = self.dm_grid.idx(m[i2] - m[i1]) {
                 a[(idx_dt, idx_dm)] += 1;
             }
+        });
+    }
+
+    /// Calls `f(idx_dt, i1, i2)` for each pair of points `i1 < i2` having `t[i2] - t[i1]` inside
+    /// the dt grid
+    ///
+    /// `t` must be an ascending slice, the inner loop stops at the first pair having dt above the
+    /// grid.
+    pub(crate) fn for_each_pair<F>(&self, t: &[T], mut f: F)
+    where
+        F: FnMut(usize, usize, usize),
+    {
+        for i1 in 0..t.len() {
+            self.for_each_later_pair(t, i1, |idx_dt, i2| f(idx_dt, i1, i2));
+        }
+    }
+
+    /// Calls `f(idx_dt, i2)` for each point `i2 > i1` having `t[i2] - t[i1]` inside the dt grid
+    pub(crate) fn for_each_later_pair<F>(&self, t: &[T], i1: usize, mut f: F)
+    where
+        F: FnMut(usize, usize),
+    {
+        let x1 = t[i1];
+        for (i2, &x2) in t.iter().enumerate().skip(i1 + 1) {
+            match self.dt_grid.idx(x2 - x1) {
+                CellIndex::LowerMin => continue,
+                CellIndex::GreaterMax => break,
+                CellIndex::Value(idx_dt) => f(idx_dt, i2),
+            }
         }
-        a
     }
 
-    fn update_gausses_helper<Erf>(
+    /// Calls `f(idx_dt, i1)` for each point `i1 < i2` having `t[i2] - t[i1]` inside the dt grid
+    pub(crate) fn for_each_earlier_pair<F>(&self, t: &[T], i2: usize, mut f: F)
+    where
+        F: FnMut(usize, usize),
+    {
+        let x2 = t[i2];
+        for (i1, &x1) in t[..i2].iter().enumerate().rev() {
+            match self.dt_grid.idx(x2 - x1) {
+                CellIndex::LowerMin => continue,
+                CellIndex::GreaterMax => break,
+                CellIndex::Value(idx_dt) => f(idx_dt, i1),
+            }
+        }
+    }
+
+    pub(crate) fn update_gausses_helper<Erf>(
         &self,
-        a: &mut Array2<T>,
+        a: &mut ArrayRef2<T>,
         idx_dt: usize,
         y1: T,
         y2: T,
@@ -212,38 +314,116 @@ where
         T: ErfFloat,
         Erf: ErrorFunction<T>,
     {
-        let dm = y2 - y1;
-        let dm_err = T::sqrt(d1 + d2);
+        self.add_weighted_gauss::<Erf>(a, idx_dt, y2 - y1, T::sqrt(d1 + d2), T::one());
+    }
 
-        let min_idx_dm = match self
-            .dm_grid
-            .idx(dm + Erf::min_dx_nonzero_normal_cdf(dm_err))
-        {
+    /// Adds `N(dm, dm_err^2)` multiplied by `weight` to the `idx_dt` row of the given map
+    pub(crate) fn add_weighted_gauss<Erf>(
+        &self,
+        a: &mut ArrayRef2<T>,
+        idx_dt: usize,
+        dm: T,
+        dm_err: T,
+        weight: T,
+    ) where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        self.add_weighted_kernel(a, idx_dt, dm, dm_err, weight, &GaussianKernel::<Erf>::new());
+    }
+
+    /// Adds `kernel` distribution located at `dm` with scale `dm_err` multiplied by `weight` to
+    /// the `idx_dt` row of the given map
+    pub(crate) fn add_weighted_kernel<K>(
+        &self,
+        a: &mut ArrayRef2<T>,
+        idx_dt: usize,
+        dm: T,
+        dm_err: T,
+        weight: T,
+        kernel: &K,
+    ) where
+        T: ErfFloat,
+        K: SmearingKernel<T>,
+    {
+        let Some((min_idx_dm, values)) =
+            Self::kernel_cell_integrals(&self.dm_grid, dm, dm_err, kernel)
+        else {
+            return;
+        };
+        a.slice_mut(s![idx_dt, min_idx_dm..])
+            .iter_mut()
+            .zip(values)
+            .for_each(|(cell, value)| *cell += weight * value);
+    }
+
+    /// Integrals of `N(x, sigma^2)` over the grid cells, see [DmDt::kernel_cell_integrals()]
+    pub(crate) fn normal_cell_integrals<'a, Erf>(
+        grid: &'a Grid<T>,
+        x: T,
+        sigma: T,
+    ) -> Option<(usize, impl Iterator<Item = T> + 'a)>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T> + 'a,
+    {
+        Self::kernel_cell_integrals(grid, x, sigma, &GaussianKernel::<Erf>::new())
+    }
+
+    /// Integrals of `kernel` distribution located at `x` with scale `sigma` over the grid cells,
+    /// see [DmDt::cdf_cell_integrals()]
+    pub(crate) fn kernel_cell_integrals<'a, K>(
+        grid: &'a Grid<T>,
+        x: T,
+        sigma: T,
+        kernel: &K,
+    ) -> Option<(usize, impl Iterator<Item = T> + use<'a, T, K>)>
+    where
+        T: ErfFloat,
+        K: SmearingKernel<T> + 'a,
+    {
+        let kernel = kernel.clone();
+        Self::cdf_cell_integrals(
+            grid,
+            x + kernel.min_dx_nonzero_cdf(sigma),
+            x + kernel.max_dx_nonunity_cdf(sigma),
+            move |border| kernel.cdf(border, x, sigma),
+        )
+    }
+
+    /// Integrals of a distribution over the grid cells
+    ///
+    /// `cdf` is the cumulative distribution function, which is zero below `min_x` and unity above
+    /// `max_x`. Returns the index of the first cell having non-zero integral and the iterator over
+    /// the integrals up to the last cell having non-zero integral, or [None] if the distribution
+    /// doesn't overlap the grid
+    pub(crate) fn cdf_cell_integrals<'a, F>(
+        grid: &'a Grid<T>,
+        min_x: T,
+        max_x: T,
+        cdf: F,
+    ) -> Option<(usize, impl Iterator<Item = T> + use<'a, T, F>)>
+    where
+        F: Fn(T) -> T + 'a,
+    {
+        let min_idx = match grid.idx(min_x) {
             CellIndex::LowerMin => 0,
-            CellIndex::GreaterMax => return,
-            CellIndex::Value(min_idx_dm) => min_idx_dm,
+            CellIndex::GreaterMax => return None,
+            CellIndex::Value(min_idx) => min_idx,
         };
-        let max_idx_dm = match self
-            .dm_grid
-            .idx(dm + Erf::max_dx_nonunity_normal_cdf(dm_err))
-        {
-            CellIndex::LowerMin => return,
-            CellIndex::GreaterMax => self.dm_grid.cell_count(),
-            CellIndex::Value(i) => usize::min(i + 1, self.dm_grid.cell_count()),
+        let max_idx = match grid.idx(max_x) {
+            CellIndex::LowerMin => return None,
+            CellIndex::GreaterMax => grid.cell_count(),
+            CellIndex::Value(i) => usize::min(i + 1, grid.cell_count()),
         };
-
-        a.slice_mut(s![idx_dt, min_idx_dm..max_idx_dm])
-            .iter_mut()
-            .zip(
-                self.dm_grid
-                    .get_borders()
-                    .slice(s![min_idx_dm..max_idx_dm + 1])
-                    .iter()
-                    .map(|&dm_border| Erf::normal_cdf(dm_border, dm, dm_err))
-                    .tuple_windows()
-                    .map(|(a, b)| b - a),
-            )
-            .for_each(|(cell, value)| *cell += value);
+        let values = grid
+            .get_borders()
+            .slice_move(s![min_idx..max_idx + 1])
+            .into_iter()
+            .map(move |&border| cdf(border))
+            .tuple_windows()
+            .map(|(a, b)| b - a);
+        Some((min_idx, values))
     }
 
     /// Represents each pair of (t, m, err2) points as a Gaussian distribution in dm-dt map
@@ -261,39 +441,142 @@ where
         Erf: ErrorFunction<T>,
     {
         let mut a = Array2::zeros(self.shape());
-        for (i1, ((&x1, &y1), &d1)) in t.iter().zip(m.iter()).zip(err2.iter()).enumerate() {
-            for ((&x2, &y2), &d2) in t[i1 + 1..]
+        self.add_gausses::<Erf>(&mut a, t, m, err2);
+        a
+    }
+
+    /// Adds Gaussian distribution of each pair of (t, m, err2) points to the given map
+    pub(crate) fn add_gausses<Erf>(&self, a: &mut ArrayRef2<T>, t: &[T], m: &[T], err2: &[T])
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        self.add_kernel_gausses(a, t, m, err2, &GaussianKernel::<Erf>::new());
+    }
+
+    /// Adds `kernel` distribution of each pair of (t, m, err2) points to the given map
+    pub(crate) fn add_kernel_gausses<K>(
+        &self,
+        a: &mut ArrayRef2<T>,
+        t: &[T],
+        m: &[T],
+        err2: &[T],
+        kernel: &K,
+    ) where
+        T: ErfFloat,
+        K: SmearingKernel<T>,
+    {
+        self.for_each_pair(t, |idx_dt, i1, i2| {
+            let dm_err = T::sqrt(err2[i1] + err2[i2]);
+            self.add_weighted_kernel(a, idx_dt, m[i2] - m[i1], dm_err, T::one(), kernel);
+        });
+    }
+
+    /// Represents each pair of (t, m, err2) points as a 2-D Gaussian distribution in dm-dt map
+    ///
+    /// `t` must be an ascending slice, `t_err2` is the squared time uncertainty of each
+    /// observation.
+    ///
+    /// It is an extension of [DmDt::gausses()] which also smears each pair along dt axis:
+    /// `dt ~ N(t2-t1, t_err2_1 + t_err2_2)` and `dm ~ N(m2-m1, err2_1 + err2_2)` are integrated
+    /// over the cells using their borders, so any dt grid, e.g. [LgGrid], is supported. Pairs
+    /// having zero dt uncertainty are not smeared along dt axis. Note that the smeared
+    /// distribution could leak out of the dt grid and could come into the grid from the pairs
+    /// having dt outside of it, so all pairs are considered.
+    pub fn gausses_2d<Erf>(&self, t: &[T], t_err2: &[T], m: &[T], err2: &[T]) -> Array2<T>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        let mut a = Array2::zeros(self.shape());
+        for (i1, (((&x1, &e1), &y1), &d1)) in t
+            .iter()
+            .zip(t_err2.iter())
+            .zip(m.iter())
+            .zip(err2.iter())
+            .enumerate()
+        {
+            for (((&x2, &e2), &y2), &d2) in t[i1 + 1..]
                 .iter()
+                .zip(t_err2[i1 + 1..].iter())
                 .zip(m[i1 + 1..].iter())
                 .zip(err2[i1 + 1..].iter())
             {
                 let dt = x2 - x1;
-                let idx_dt = match self.dt_grid.idx(dt) {
-                    CellIndex::LowerMin => continue,
-                    CellIndex::GreaterMax => break,
-                    CellIndex::Value(idx_dt) => idx_dt,
+                let dt_err = T::sqrt(e1 + e2);
+                let dm = y2 - y1;
+                let dm_err = T::sqrt(d1 + d2);
+                if dt_err.is_zero() {
+                    if let CellIndex::Value(idx_dt) = self.dt_grid.idx(dt) {
+                        self.add_weighted_gauss::<Erf>(&mut a, idx_dt, dm, dm_err, T::one());
+                    }
+                    continue;
+                }
+                let Some((min_idx_dt, dt_values)) =
+                    Self::normal_cell_integrals::<Erf>(&self.dt_grid, dt, dt_err)
+                else {
+                    continue;
                 };
-                self.update_gausses_helper::<Erf>(&mut a, idx_dt, y1, y2, d1, d2);
+                for (idx_dt, weight) in (min_idx_dt..).zip(dt_values) {
+                    self.add_weighted_gauss::<Erf>(&mut a, idx_dt, dm, dm_err, weight);
+                }
             }
         }
         a
     }
 
     /// Count dt in the each dt grid cell
+    ///
+    /// `t` must be an ascending slice. It doesn't iterate over all pairs of points, but moves a
+    /// pointer to the first point of each dt cell for each point, so the time complexity is
+    /// `O(N * N_dt)` rather than `O(N^2)`. Light curves having less points than dt cells are
+    /// processed pair by pair, which is faster for them.
     pub fn dt_points(&self, t: &[T]) -> Array1<u64> {
         let mut a = Array1::zeros(self.dt_grid.cell_count());
+        self.add_dt_points(&mut a, t);
+        a
+    }
+
+    /// Adds number of pairs in each dt cell to the given array
+    pub(crate) fn add_dt_points(&self, a: &mut ArrayRef1<u64>, t: &[T]) {
+        if t.len() < self.dt_grid.cell_count() {
+            self.for_each_pair(t, |idx_dt, _i1, _i2| a[idx_dt] += 1);
+            return;
+        }
+        self.for_each_dt_cell_range(t, |_i1, idx_dt, range| a[idx_dt] += range.len() as u64);
+    }
+
+    /// Calls `f(i1, idx_dt, range)` for each point and each dt cell, where `range` is the range of
+    /// indices `i2` of the points having `t[i2] - t[i1]` in the cell
+    ///
+    /// `t` must be an ascending slice. It moves a pointer to the first point of each dt cell for
+    /// each point of the light curve instead of iterating over all pairs.
+    pub(crate) fn for_each_dt_cell_range<F>(&self, t: &[T], mut f: F)
+    where
+        F: FnMut(usize, usize, Range<usize>),
+    {
+        let n_dt = self.dt_grid.cell_count();
+        // Rank of dt: 0 for dt below the grid, idx + 1 for dt inside, n_dt + 1 for dt above
+        let rank = |dt: T| match self.dt_grid.idx(dt) {
+            CellIndex::LowerMin => 0,
+            CellIndex::GreaterMax => n_dt + 1,
+            CellIndex::Value(idx_dt) => idx_dt + 1,
+        };
+        // first_idx[r] is the index of the first point having rank of dt at least r. Rank is
+        // non-increasing with respect to the first point of the pair, so these indices never
+        // decrease and total number of pointer moves is O(N * N_dt).
+        let mut first_idx = vec![0; n_dt + 2];
         for (i1, &x1) in t.iter().enumerate() {
-            for &x2 in t[i1 + 1..].iter() {
-                let dt = x2 - x1;
-                let idx_dt = match self.dt_grid.idx(dt) {
-                    CellIndex::LowerMin => continue,
-                    CellIndex::GreaterMax => break,
-                    CellIndex::Value(idx_dt) => idx_dt,
-                };
-                a[idx_dt] += 1;
+            for (r, first) in first_idx.iter_mut().enumerate().skip(1) {
+                *first = usize::max(*first, i1 + 1);
+                while *first < t.len() && rank(t[*first] - x1) < r {
+                    *first += 1;
+                }
+            }
+            for (idx_dt, w) in first_idx[1..].windows(2).enumerate() {
+                f(i1, idx_dt, w[0]..w[1]);
             }
         }
-        a
     }
 
     /// Conditional probability `p(m2-m1|t2-t1)`
@@ -308,34 +591,40 @@ where
         Erf: ErrorFunction<T>,
     {
         let mut a: Array2<T> = Array2::zeros(self.shape());
-        let mut dt_points: Array1<u64> = Array1::zeros(self.dt_grid.cell_count());
-        for (i1, ((&x1, &y1), &d1)) in t.iter().zip(m.iter()).zip(err2.iter()).enumerate() {
-            for ((&x2, &y2), &d2) in t[i1 + 1..]
-                .iter()
-                .zip(m[i1 + 1..].iter())
-                .zip(err2[i1 + 1..].iter())
-            {
-                let dt = x2 - x1;
-                let idx_dt = match self.dt_grid.idx(dt) {
-                    CellIndex::LowerMin => continue,
-                    CellIndex::GreaterMax => break,
-                    CellIndex::Value(idx_dt) => idx_dt,
-                };
+        self.fill_cond_prob::<Erf>(&mut a, t, m, err2);
+        a
+    }
 
-                dt_points[idx_dt] += 1;
+    /// Writes conditional probability `p(m2-m1|t2-t1)` into the given map, which must be zeroed
+    pub(crate) fn fill_cond_prob<Erf>(&self, a: &mut ArrayRef2<T>, t: &[T], m: &[T], err2: &[T])
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        self.fill_kernel_cond_prob(a, t, m, err2, &GaussianKernel::<Erf>::new());
+    }
 
-                self.update_gausses_helper::<Erf>(&mut a, idx_dt, y1, y2, d1, d2);
-            }
-        }
-        ndarray::Zip::from(a.rows_mut())
-            .and(&dt_points)
-            .for_each(|mut row, &count| {
-                if count == 0 {
-                    return;
-                }
-                row /= T::approx_from(count).unwrap();
-            });
-        a
+    /// Writes conditional probability `p(m2-m1|t2-t1)` smeared with `kernel` into the given map,
+    /// which must be zeroed
+    pub(crate) fn fill_kernel_cond_prob<K>(
+        &self,
+        a: &mut ArrayRef2<T>,
+        t: &[T],
+        m: &[T],
+        err2: &[T],
+        kernel: &K,
+    ) where
+        T: ErfFloat,
+        K: SmearingKernel<T>,
+    {
+        let mut dt_points: Array1<u64> = Array1::zeros(self.dt_grid.cell_count());
+        self.for_each_pair(t, |idx_dt, i1, i2| {
+            dt_points[idx_dt] += 1;
+
+            let dm_err = T::sqrt(err2[i1] + err2[i2]);
+            self.add_weighted_kernel(a, idx_dt, m[i2] - m[i1], dm_err, T::one(), kernel);
+        });
+        normalise_by_dt_points(a, &dt_points);
     }
 }
 
@@ -366,6 +655,38 @@ mod test {
         assert_eq!(points.sum_axis(Axis(1)), dt_points,);
     }
 
+    #[test]
+    fn dt_points_vs_all_pairs() {
+        // Irregular time with repeated values and dt lying on the cell borders
+        let t: Vec<f64> = (0..300)
+            .map(|i| f64::floor(100.0 * f64::sin(i as f64).abs()) + (i / 3) as f64)
+            .sorted_by(|a, b| a.partial_cmp(b).unwrap())
+            .collect();
+        let grids: [DmDt<f64>; 3] = [
+            DmDt::from_lgdt_dm_limits(0.0, 2.0, 32, 3.0, 32),
+            DmDt::from_grids(
+                LinearGrid::new(1.0, 65.0, 16),
+                LinearGrid::new(-1.0, 1.0, 2),
+            ),
+            // More dt cells than points
+            DmDt::from_grids(
+                LinearGrid::new(1.0, 257.0, 512),
+                LinearGrid::new(-1.0, 1.0, 2),
+            ),
+        ];
+        for dmdt in grids {
+            let mut desired = Array1::zeros(dmdt.dt_grid.cell_count());
+            for (i1, &x1) in t.iter().enumerate() {
+                for &x2 in t[i1 + 1..].iter() {
+                    if let CellIndex::Value(idx_dt) = dmdt.dt_grid.idx(x2 - x1) {
+                        desired[idx_dt] += 1;
+                    }
+                }
+            }
+            assert_eq!(dmdt.dt_points(&t), desired);
+        }
+    }
+
     #[test]
     fn dt_points_vs_gausses() {
         let dmdt = DmDt::from_lgdt_dm_limits(0.0_f32, 2.0_f32, 32, 3.0_f32, 32);
@@ -423,4 +744,44 @@ mod test {
             epsilon = f32::EPSILON,
         );
     }
+
+    #[test]
+    fn gausses_2d_vs_gausses() {
+        let dmdt = DmDt::from_lgdt_dm_limits(-1.0_f64, 2.5_f64, 32, 3.0_f64, 32);
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let m = t.mapv(f64::sin);
+        // err is ~0.03
+        let err2 = Array1::from_elem(101, 0.001);
+
+        let gausses = dmdt.gausses::<ExactErf>(
+            t.as_slice().unwrap(),
+            m.as_slice().unwrap(),
+            err2.as_slice().unwrap(),
+        );
+
+        let zero_t_err2 = Array1::zeros(101);
+        let gausses_2d_zero_t_err = dmdt.gausses_2d::<ExactErf>(
+            t.as_slice().unwrap(),
+            zero_t_err2.as_slice().unwrap(),
+            m.as_slice().unwrap(),
+            err2.as_slice().unwrap(),
+        );
+        assert_eq!(gausses_2d_zero_t_err, gausses);
+
+        let t_err2 = Array1::from_elem(101, 0.01);
+        let gausses_2d = dmdt.gausses_2d::<ExactErf>(
+            t.as_slice().unwrap(),
+            t_err2.as_slice().unwrap(),
+            m.as_slice().unwrap(),
+            err2.as_slice().unwrap(),
+        );
+        // Smearing along dt conserves the probability, all pairs are far from dt grid edges
+        assert_abs_diff_eq!(gausses_2d.sum(), gausses.sum(), epsilon = 1e-6);
+        assert_abs_diff_eq!(
+            gausses_2d.sum_axis(Axis(0)).as_slice().unwrap(),
+            gausses.sum_axis(Axis(0)).as_slice().unwrap(),
+            epsilon = 1e-6,
+        );
+        assert_ne!(gausses_2d, gausses);
+    }
 }
@@ -0,0 +1,231 @@
+use crate::norm::normalise_by_dt_points;
+use crate::{CellIndex, DmDt, ErfFloat, ErrorFunction, Float, GridTrait, LgGrid, LinearGrid};
+
+use ndarray::{Array1, Array2};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Flux difference used as the vertical axis of df–dt maps
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FluxDiff {
+    /// Absolute flux difference `f2 - f1`
+    Absolute,
+    /// Relative flux change `(f2 - f1) / (f1 + f2)`
+    ///
+    /// It is in `[-1, 1]` interval for non-negative fluxes, and could be outside of it if some of
+    /// the fluxes are negative. Pairs having `f1 + f2 = 0` are skipped.
+    Relative,
+}
+
+impl FluxDiff {
+    fn value<T>(self, f1: T, f2: T) -> T
+    where
+        T: Float,
+    {
+        match self {
+            Self::Absolute => f2 - f1,
+            Self::Relative => (f2 - f1) / (f1 + f2),
+        }
+    }
+
+    /// Value and its uncertainty propagated from `d1` and `d2` squared flux errors
+    ///
+    /// Relative flux change uncertainty is linear error propagation:
+    /// `4 (f2^2 d1 + f1^2 d2) / (f1 + f2)^4`.
+    fn value_err<T>(self, f1: T, f2: T, d1: T, d2: T) -> (T, T)
+    where
+        T: Float,
+    {
+        match self {
+            Self::Absolute => (f2 - f1, T::sqrt(d1 + d2)),
+            Self::Relative => {
+                let sum = f1 + f2;
+                let err = (T::one() + T::one()) * T::sqrt(f2.powi(2) * d1 + f1.powi(2) * d2)
+                    / sum.powi(2);
+                ((f2 - f1) / sum, err)
+            }
+        }
+    }
+}
+
+impl<T> DmDt<T>
+where
+    T: Float,
+{
+    /// Create new [DmDt] with logarithmic dt grid and linear absolute flux difference grid
+    ///
+    /// dt grid will have borders `[10^min_lgdt, 10^max_lgdt)`, df grid will have borders
+    /// `[-max_abs_df, max_abs_df)`. Use it with [FluxDiff::Absolute].
+    pub fn from_lgdt_abs_df_limits(
+        min_lgdt: T,
+        max_lgdt: T,
+        lgdt_size: usize,
+        max_abs_df: T,
+        df_size: usize,
+    ) -> Self {
+        Self::from_grids(
+            LgGrid::from_lg_start_end(min_lgdt, max_lgdt, lgdt_size),
+            LinearGrid::new(-max_abs_df, max_abs_df, df_size),
+        )
+    }
+
+    /// Create new [DmDt] with logarithmic dt grid and linear relative flux change grid
+    ///
+    /// dt grid will have borders `[10^min_lgdt, 10^max_lgdt)`, df grid will have borders
+    /// `[-1, 1)`, which covers all possible values of [FluxDiff::Relative] for non-negative fluxes
+    /// except `1` corresponding to zero `f1`.
+    pub fn from_lgdt_rel_df(min_lgdt: T, max_lgdt: T, lgdt_size: usize, df_size: usize) -> Self {
+        Self::from_grids(
+            LgGrid::from_lg_start_end(min_lgdt, max_lgdt, lgdt_size),
+            LinearGrid::new(-T::one(), T::one(), df_size),
+        )
+    }
+
+    /// Represents each pair of (t, f) points as a unity value in df-dt map
+    ///
+    /// `t` must be an ascending slice. It is a flux counterpart of [DmDt::points()], `diff`
+    /// specifies the flux difference to use as the vertical axis.
+    pub fn flux_points(&self, t: &[T], f: &[T], diff: FluxDiff) -> Array2<u64> {
+        let mut a = Array2::zeros(self.shape());
+        self.for_each_pair(t, |idx_dt, i1, i2| {
+            let df = diff.value(f[i1], f[i2]);
+            if !df.is_finite() {
+                return;
+            }
+            if let CellIndex::Value(idx_df) = self.dm_grid.idx(df) {
+                a[(idx_dt, idx_df)] += 1;
+            }
+        });
+        a
+    }
+
+    /// Calls `f(idx_dt, df, df_err)` for each pair of (t, f, f_err2) points
+    fn for_each_flux_pair<F>(&self, t: &[T], f: &[T], f_err2: &[T], diff: FluxDiff, mut func: F)
+    where
+        F: FnMut(usize, T, T),
+    {
+        self.for_each_pair(t, |idx_dt, i1, i2| {
+            let (df, df_err) = diff.value_err(f[i1], f[i2], f_err2[i1], f_err2[i2]);
+            if df.is_finite() && df_err.is_finite() {
+                func(idx_dt, df, df_err);
+            }
+        });
+    }
+
+    /// Represents each pair of (t, f, f_err2) points as a Gaussian distribution in df-dt map
+    ///
+    /// `t` must be an ascending slice. It is a flux counterpart of [DmDt::gausses()], `diff`
+    /// specifies the flux difference to use as the vertical axis. Its uncertainty is propagated
+    /// from the flux errors linearly, so the distribution is Gaussian for both
+    /// [FluxDiff::Absolute] and [FluxDiff::Relative].
+    pub fn flux_gausses<Erf>(&self, t: &[T], f: &[T], f_err2: &[T], diff: FluxDiff) -> Array2<T>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        let mut a = Array2::zeros(self.shape());
+        self.for_each_flux_pair(t, f, f_err2, diff, |idx_dt, df, df_err| {
+            self.add_weighted_gauss::<Erf>(&mut a, idx_dt, df, df_err, T::one());
+        });
+        a
+    }
+
+    /// Conditional probability `p(df|t2-t1)`
+    ///
+    /// It is a flux counterpart of [DmDt::cond_prob()]: [DmDt::flux_gausses()] normalized by the
+    /// number of pairs in each dt cell. Pairs skipped because of zero `f1 + f2` for
+    /// [FluxDiff::Relative] are not counted.
+    pub fn flux_cond_prob<Erf>(&self, t: &[T], f: &[T], f_err2: &[T], diff: FluxDiff) -> Array2<T>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        let mut a = Array2::zeros(self.shape());
+        let mut dt_points: Array1<u64> = Array1::zeros(self.dt_grid.cell_count());
+        self.for_each_flux_pair(t, f, f_err2, diff, |idx_dt, df, df_err| {
+            dt_points[idx_dt] += 1;
+            self.add_weighted_gauss::<Erf>(&mut a, idx_dt, df, df_err, T::one());
+        });
+        normalise_by_dt_points(&mut a, &dt_points);
+        a
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::ExactErf;
+
+    use approx::assert_abs_diff_eq;
+    use ndarray::Axis;
+
+    #[test]
+    fn absolute_flux_vs_magnitudes() {
+        let dmdt = DmDt::from_lgdt_abs_df_limits(0.0_f64, 2.0_f64, 32, 3.0_f64, 32);
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let f = t.mapv(f64::sin);
+        let f_err2 = Array1::from_elem(101, 0.001);
+        let (t, f, f_err2) = (
+            t.as_slice().unwrap(),
+            f.as_slice().unwrap(),
+            f_err2.as_slice().unwrap(),
+        );
+
+        assert_eq!(
+            dmdt.flux_points(t, f, FluxDiff::Absolute),
+            dmdt.points(t, f)
+        );
+        assert_eq!(
+            dmdt.flux_gausses::<ExactErf>(t, f, f_err2, FluxDiff::Absolute),
+            dmdt.gausses::<ExactErf>(t, f, f_err2)
+        );
+        assert_eq!(
+            dmdt.flux_cond_prob::<ExactErf>(t, f, f_err2, FluxDiff::Absolute),
+            dmdt.cond_prob::<ExactErf>(t, f, f_err2)
+        );
+    }
+
+    #[test]
+    fn relative_flux() {
+        let dmdt = DmDt::from_lgdt_rel_df(0.0_f64, 2.0_f64, 32, 32);
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let f = t.mapv(|x| 2.0 + f64::sin(x));
+        let f_err2 = Array1::from_elem(101, 1e-4);
+        let (t, f, f_err2) = (
+            t.as_slice().unwrap(),
+            f.as_slice().unwrap(),
+            f_err2.as_slice().unwrap(),
+        );
+
+        let dt_points = dmdt.dt_points(t);
+        let points = dmdt.flux_points(t, f, FluxDiff::Relative);
+        assert_eq!(points.sum_axis(Axis(1)), dt_points);
+
+        let cond_prob = dmdt.flux_cond_prob::<ExactErf>(t, f, f_err2, FluxDiff::Relative);
+        for (&count, row) in dt_points.iter().zip(cond_prob.rows()) {
+            let desired = if count == 0 { 0.0 } else { 1.0 };
+            assert_abs_diff_eq!(row.sum(), desired, epsilon = 1e-10);
+        }
+    }
+
+    #[test]
+    fn relative_flux_zero_sum() {
+        let dmdt = DmDt::from_lgdt_rel_df(0.0_f64, 2.0_f64, 32, 32);
+        let t = Array1::linspace(0.0, 100.0, 101);
+        // Non-negative fluxes with many zeros
+        let f = t.mapv(|x| f64::max(f64::sin(x), 0.0));
+        let f_err2 = Array1::from_elem(101, 1e-4);
+        let (t, f, f_err2) = (
+            t.as_slice().unwrap(),
+            f.as_slice().unwrap(),
+            f_err2.as_slice().unwrap(),
+        );
+
+        let gausses = dmdt.flux_gausses::<ExactErf>(t, f, f_err2, FluxDiff::Relative);
+        assert!(gausses.iter().all(|x| x.is_finite()));
+        let cond_prob = dmdt.flux_cond_prob::<ExactErf>(t, f, f_err2, FluxDiff::Relative);
+        assert!(cond_prob.iter().all(|x| x.is_finite()));
+    }
+}
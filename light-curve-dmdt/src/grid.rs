@@ -1,4 +1,4 @@
-use crate::Float;
+use crate::{DmDt, Float};
 
 use conv::{ConvAsUtil, ConvUtil, RoundToZero};
 use enum_dispatch::enum_dispatch;
@@ -39,6 +39,9 @@ where
 }
 
 /// Grid for dm or dt axis
+///
+/// With `serde` feature it is serialisable unless it is [TransformGrid] with [Transform::Custom],
+/// serialising such a grid returns an error.
 #[enum_dispatch(GridTrait<T>)]
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -50,6 +53,9 @@ where
     Array(ArrayGrid<T>),
     Linear(LinearGrid<T>),
     Lg(LgGrid<T>),
+    SymLog(SymLogGrid<T>),
+    Asinh(AsinhGrid<T>),
+    Transform(TransformGrid<T>),
 }
 
 impl<T> Grid<T>
@@ -71,6 +77,18 @@ where
     pub fn log_from_lg_start_end(lg_start: T, lg_end: T, n: usize) -> Self {
         LgGrid::from_lg_start_end(lg_start, lg_end, n).into()
     }
+
+    pub fn symlog(start: T, end: T, n: usize, linthresh: T) -> Self {
+        SymLogGrid::new(start, end, n, linthresh).into()
+    }
+
+    pub fn asinh(start: T, end: T, n: usize, scale: T) -> Self {
+        AsinhGrid::new(start, end, n, scale).into()
+    }
+
+    pub fn transform(start: T, end: T, n: usize, transform: Transform<T>) -> Self {
+        TransformGrid::new(start, end, n, transform).into()
+    }
 }
 
 /// An error to be returned from grid constructors
@@ -80,6 +98,33 @@ pub enum ArrayGridError {
     ArrayIsEmpty,
     #[error("given grid is not ascending")]
     ArrayIsNotAscending,
+    #[error("quantile grid has no cells, all values are the same or min_width is too large")]
+    QuantileGridHasNoCells,
+}
+
+/// Options for quantile grid constructors, see [ArrayGrid::from_quantiles()]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct QuantileGridOptions<T> {
+    /// Minimum cell width, narrower cells are merged with their right neighbours, the rightmost
+    /// cell is merged with its left neighbour
+    pub min_width: T,
+    /// Merge duplicate borders, which appear when many values are the same, otherwise
+    /// [ArrayGridError::ArrayIsNotAscending] is returned if they are not merged because of
+    /// `min_width`
+    pub merge_duplicates: bool,
+}
+
+impl<T> Default for QuantileGridOptions<T>
+where
+    T: Float,
+{
+    fn default() -> Self {
+        Self {
+            min_width: T::zero(),
+            merge_duplicates: true,
+        }
+    }
 }
 
 /// Grid which cell borders are defined by an ascending array
@@ -107,6 +152,90 @@ where
         }
         Ok(Self { borders })
     }
+
+    /// Create [ArrayGrid] having `n` cells with equal number of `values` in them
+    ///
+    /// Borders are the quantiles of `values`, linearly interpolated between the closest ranks,
+    /// so the first border is the minimum value, and the last border is slightly larger than the
+    /// maximum value, so all `values` are inside the grid. Cells could be merged according to
+    /// `options`, so the grid could have less than `n` cells.
+    ///
+    /// # Panics
+    /// Panics if `n` is zero or `values` has NaN
+    pub fn from_quantiles(
+        values: &[T],
+        n: usize,
+        options: QuantileGridOptions<T>,
+    ) -> Result<Self, ArrayGridError> {
+        assert!(n > 0);
+        if values.is_empty() {
+            return Err(ArrayGridError::ArrayIsEmpty);
+        }
+        let mut values = values.to_vec();
+        crate::util::sort_floats(&mut values);
+        let n_float = n.value_as::<T>().unwrap();
+        let quantiles = (0..=n)
+            .map(|i| crate::util::quantile_sorted(&values, i.value_as::<T>().unwrap() / n_float));
+
+        let mut borders: Vec<T> = Vec::with_capacity(n + 1);
+        for x in quantiles {
+            match borders.last() {
+                Some(&last) if options.merge_duplicates && x == last => {}
+                Some(&last) if x - last < options.min_width => {}
+                _ => borders.push(x),
+            }
+        }
+        let end = values[values.len() - 1];
+        if *borders.last().unwrap() != end {
+            // The rightmost cell is too narrow, merge it with its left neighbour
+            if borders.len() > 1 {
+                borders.pop();
+            }
+            borders.push(end);
+        }
+        if borders.len() < 2 || borders[borders.len() - 1] - borders[0] < options.min_width {
+            return Err(ArrayGridError::QuantileGridHasNoCells);
+        }
+        // Cells don't include their right borders, so the maximum is moved into the rightmost cell
+        let last = borders.last_mut().unwrap();
+        *last += T::max(last.abs() * T::epsilon(), T::min_positive_value());
+        Self::new(borders.into())
+    }
+
+    /// Create [ArrayGrid] having `n` cells with equal number of pair time differences in them
+    ///
+    /// `t_population` is a set of light curve time slices, each must be ascending. Time
+    /// differences of all pairs of observations of each light curve are used, see
+    /// [ArrayGrid::from_quantiles()] for details.
+    pub fn from_dt_quantiles(
+        t_population: &[&[T]],
+        n: usize,
+        options: QuantileGridOptions<T>,
+    ) -> Result<Self, ArrayGridError> {
+        let dt: Vec<_> = t_population
+            .iter()
+            .flat_map(|t| DmDt::sorted_dt(t))
+            .collect();
+        Self::from_quantiles(&dt, n, options)
+    }
+
+    /// Create [ArrayGrid] having `n` cells with equal number of pair magnitude differences in
+    /// them
+    ///
+    /// `m_population` is a set of light curve magnitude slices, each must be sorted by time.
+    /// Magnitude differences of all pairs of observations of each light curve are used, see
+    /// [ArrayGrid::from_quantiles()] for details.
+    pub fn from_dm_quantiles(
+        m_population: &[&[T]],
+        n: usize,
+        options: QuantileGridOptions<T>,
+    ) -> Result<Self, ArrayGridError> {
+        let dm: Vec<_> = m_population
+            .iter()
+            .flat_map(|m| DmDt::sorted_dm(m))
+            .collect();
+        Self::from_quantiles(&dm, n, options)
+    }
 }
 
 impl<T> GridTrait<T> for ArrayGrid<T>
@@ -333,6 +462,347 @@ where
     }
 }
 
+/// Symmetric logarithmic grid defined by its start, end, number of cells and linear threshold
+///
+/// Cells are equal in the coordinate which is linear inside `[-linthresh, linthresh]` and
+/// logarithmic outside, each decade adds unity to it: `x / linthresh` for `|x| < linthresh` and
+/// `sign(x) (1 + lg(|x| / linthresh))` otherwise. It is useful for dm axis having most of the
+/// pairs near zero and long tails. It is [TransformGrid] with [Transform::SymLog].
+///
+/// Lookup time is O(1)
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SymLogGrid<T> {
+    linthresh: T,
+    grid: TransformGrid<T>,
+}
+
+impl<T> SymLogGrid<T>
+where
+    T: Float,
+{
+    /// Create [SymLogGrid] from borders, number of cells and linear threshold
+    ///
+    /// `start` is the left border of the leftmost cell, `end` is the right border of the rightmost
+    /// cell, `n` is the number of cells, `linthresh` is the half-width of the linear part.
+    pub fn new(start: T, end: T, n: usize, linthresh: T) -> Self {
+        assert!(linthresh.is_positive());
+        Self {
+            linthresh,
+            grid: TransformGrid::new(start, end, n, Transform::SymLog { linthresh }),
+        }
+    }
+
+    /// Coordinate in which cells are equal
+    pub(crate) fn transform(&self, x: T) -> T {
+        self.grid.transform(x)
+    }
+
+    /// Half-width of the linear part
+    #[inline]
+    pub fn get_linthresh(&self) -> T {
+        self.linthresh
+    }
+
+    /// Cell size in the symmetric logarithmic coordinate
+    #[inline]
+    pub fn get_cell_symlog_size(&self) -> T {
+        self.grid.get_cell_transformed_size()
+    }
+}
+
+impl<T> GridTrait<T> for SymLogGrid<T>
+where
+    T: Float,
+{
+    #[inline]
+    fn get_borders(&self) -> ArrayView1<'_, T> {
+        self.grid.get_borders()
+    }
+
+    #[inline]
+    fn cell_count(&self) -> usize {
+        self.grid.cell_count()
+    }
+
+    #[inline]
+    fn get_start(&self) -> T {
+        self.grid.get_start()
+    }
+
+    #[inline]
+    fn get_end(&self) -> T {
+        self.grid.get_end()
+    }
+
+    #[inline]
+    fn idx(&self, x: T) -> CellIndex {
+        self.grid.idx(x)
+    }
+}
+
+/// Inverse hyperbolic sine grid defined by its start, end, number of cells and scale
+///
+/// Cells are equal in `asinh(x / scale)` coordinate, so they are nearly linear for
+/// `|x| << scale` and nearly logarithmic for `|x| >> scale`. It is a smooth alternative to
+/// [SymLogGrid]. It is [TransformGrid] with [Transform::Asinh].
+///
+/// Lookup time is O(1)
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AsinhGrid<T> {
+    scale: T,
+    grid: TransformGrid<T>,
+}
+
+impl<T> AsinhGrid<T>
+where
+    T: Float,
+{
+    /// Create [AsinhGrid] from borders, number of cells and scale
+    ///
+    /// `start` is the left border of the leftmost cell, `end` is the right border of the rightmost
+    /// cell, `n` is the number of cells, `scale` is the width of the nearly linear part.
+    pub fn new(start: T, end: T, n: usize, scale: T) -> Self {
+        assert!(scale.is_positive());
+        Self {
+            scale,
+            grid: TransformGrid::new(start, end, n, Transform::Asinh { scale }),
+        }
+    }
+
+    /// Coordinate in which cells are equal
+    pub(crate) fn transform(&self, x: T) -> T {
+        self.grid.transform(x)
+    }
+
+    /// Scale of the nearly linear part
+    #[inline]
+    pub fn get_scale(&self) -> T {
+        self.scale
+    }
+
+    /// Cell size in `asinh(x / scale)` coordinate
+    #[inline]
+    pub fn get_cell_asinh_size(&self) -> T {
+        self.grid.get_cell_transformed_size()
+    }
+}
+
+impl<T> GridTrait<T> for AsinhGrid<T>
+where
+    T: Float,
+{
+    #[inline]
+    fn get_borders(&self) -> ArrayView1<'_, T> {
+        self.grid.get_borders()
+    }
+
+    #[inline]
+    fn cell_count(&self) -> usize {
+        self.grid.cell_count()
+    }
+
+    #[inline]
+    fn get_start(&self) -> T {
+        self.grid.get_start()
+    }
+
+    #[inline]
+    fn get_end(&self) -> T {
+        self.grid.get_end()
+    }
+
+    #[inline]
+    fn idx(&self, x: T) -> CellIndex {
+        self.grid.idx(x)
+    }
+}
+
+/// Monotonically increasing transform for [TransformGrid]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Transform<T> {
+    /// `x`, it gives the same borders as [LinearGrid]
+    Linear,
+    /// `lg(x + offset)`, it gives the same borders as [LgGrid] for zero offset
+    Lg { offset: T },
+    /// `sqrt(x)`, defined for non-negative `x`
+    Sqrt,
+    /// `sign(x) |x|^exponent`, `exponent` must be positive
+    Power { exponent: T },
+    /// `asinh(x / scale)`, see [AsinhGrid]
+    Asinh { scale: T },
+    /// `x / linthresh` inside `[-linthresh, linthresh]` and `sign(x) (1 + lg(|x| / linthresh))`
+    /// outside, see [SymLogGrid]
+    SymLog { linthresh: T },
+    /// User-defined transform, `inverse` must be the inverse function of `forward`
+    ///
+    /// It is skipped by `serde`, so serialising it returns an error.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom {
+        forward: fn(T) -> T,
+        inverse: fn(T) -> T,
+    },
+}
+
+impl<T> Transform<T>
+where
+    T: Float,
+{
+    /// Transformed coordinate
+    pub fn forward(&self, x: T) -> T {
+        match *self {
+            Self::Linear => x,
+            Self::Lg { offset } => (x + offset).log10(),
+            Self::Sqrt => x.sqrt(),
+            Self::Power { exponent } => x.signum() * x.abs().powf(exponent),
+            Self::Asinh { scale } => (x / scale).asinh(),
+            Self::SymLog { linthresh } => {
+                let y = x.abs() / linthresh;
+                if y < T::one() {
+                    x / linthresh
+                } else {
+                    x.signum() * (T::one() + y.log10())
+                }
+            }
+            Self::Custom { forward, .. } => forward(x),
+        }
+    }
+
+    /// Original coordinate
+    pub fn inverse(&self, u: T) -> T {
+        match *self {
+            Self::Linear => u,
+            Self::Lg { offset } => T::ten().powf(u) - offset,
+            Self::Sqrt => u.powi(2),
+            Self::Power { exponent } => u.signum() * u.abs().powf(exponent.recip()),
+            Self::Asinh { scale } => scale * u.sinh(),
+            Self::SymLog { linthresh } => {
+                if u.abs() < T::one() {
+                    u * linthresh
+                } else {
+                    u.signum() * linthresh * T::ten().powf(u.abs() - T::one())
+                }
+            }
+            Self::Custom { inverse, .. } => inverse(u),
+        }
+    }
+}
+
+/// Grid having equal cells in the given transformed coordinate
+///
+/// It is a generalisation of [LinearGrid] and [LgGrid], see [Transform] for available
+/// transforms.
+///
+/// Lookup time is O(1) plus the time of [Transform::forward()]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TransformGrid<T> {
+    start: T,
+    end: T,
+    transform: Transform<T>,
+    u_start: T,
+    n: usize,
+    cell_u_size: T,
+    borders: Array1<T>,
+}
+
+impl<T> TransformGrid<T>
+where
+    T: Float,
+{
+    /// Create [TransformGrid] from borders, number of cells and transform
+    ///
+    /// `start` is the left border of the leftmost cell, `end` is the right border of the rightmost
+    /// cell, `n` is the number of cells. `transform` must be increasing on `[start, end]`.
+    ///
+    /// # Panics
+    /// Panics if `start` is not less than `end`, or if `transform` is not finite or not increasing
+    /// at `start` and `end`, e.g. `lg(x)` at zero
+    pub fn new(start: T, end: T, n: usize, transform: Transform<T>) -> Self {
+        assert!(end > start);
+        let u_start = transform.forward(start);
+        let u_end = transform.forward(end);
+        assert!(u_start.is_finite());
+        assert!(u_end.is_finite());
+        assert!(u_end > u_start);
+        let cell_u_size = (u_end - u_start) / n.value_as::<T>().unwrap();
+        let mut borders = Array1::linspace(u_start, u_end, n + 1).mapv(|u| transform.inverse(u));
+        borders[0] = start;
+        borders[n] = end;
+        Self {
+            start,
+            end,
+            transform,
+            u_start,
+            n,
+            cell_u_size,
+            borders,
+        }
+    }
+
+    /// Coordinate in which cells are equal
+    pub(crate) fn transform(&self, x: T) -> T {
+        self.transform.forward(x)
+    }
+
+    /// Transform of the grid
+    #[inline]
+    pub fn get_transform(&self) -> &Transform<T> {
+        &self.transform
+    }
+
+    /// Cell size in the transformed coordinate
+    #[inline]
+    pub fn get_cell_transformed_size(&self) -> T {
+        self.cell_u_size
+    }
+}
+
+impl<T> GridTrait<T> for TransformGrid<T>
+where
+    T: Float,
+{
+    #[inline]
+    fn get_borders(&self) -> ArrayView1<'_, T> {
+        self.borders.view()
+    }
+
+    #[inline]
+    fn cell_count(&self) -> usize {
+        self.n
+    }
+
+    #[inline]
+    fn get_start(&self) -> T {
+        self.start
+    }
+
+    #[inline]
+    fn get_end(&self) -> T {
+        self.end
+    }
+
+    fn idx(&self, x: T) -> CellIndex {
+        if x < self.start {
+            return CellIndex::LowerMin;
+        }
+        if x >= self.end {
+            return CellIndex::GreaterMax;
+        }
+        let i = ((self.transform(x) - self.u_start) / self.cell_u_size)
+            .approx_by::<RoundToZero>()
+            .unwrap();
+        if i < self.n {
+            CellIndex::Value(i)
+        } else {
+            // x is a bit smaller self.end + float rounding
+            CellIndex::Value(self.n - 1)
+        }
+    }
+}
+
 /// Value to return from [GridTrait::idx]
 pub enum CellIndex {
     /// Bellow the leftmost border
@@ -342,3 +812,182 @@ pub enum CellIndex {
     /// Cell index
     Value(usize),
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use approx::assert_relative_eq;
+    use ndarray::s;
+
+    /// Checks that `idx` of the grid agrees with bisection over its borders
+    fn check_idx(grid: &Grid<f64>) {
+        let array_grid = ArrayGrid::new(grid.get_borders().to_owned()).unwrap();
+        let (start, end) = (grid.get_start(), grid.get_end());
+        for x in Array1::linspace(start - 1.0, end + 1.0, 1001) {
+            match (grid.idx(x), array_grid.idx(x)) {
+                (CellIndex::LowerMin, CellIndex::LowerMin)
+                | (CellIndex::GreaterMax, CellIndex::GreaterMax) => {}
+                (CellIndex::Value(i), CellIndex::Value(j)) => assert_eq!(i, j, "x = {x}"),
+                _ => panic!("x = {x}"),
+            }
+        }
+    }
+
+    #[test]
+    fn symlog() {
+        let grid = Grid::symlog(-3.0, 3.0, 8, 0.1);
+        let borders = grid.get_borders();
+        // Grid with symmetric limits is symmetric
+        for (a, b) in borders.iter().zip(borders.iter().rev()) {
+            assert_relative_eq!(*a, -*b, max_relative = 1e-12);
+        }
+        let grid = Grid::symlog(-100.0, 100.0, 6, 1.0);
+        assert_relative_eq!(
+            grid.get_borders().as_slice().unwrap(),
+            &[-100.0, -10.0, -1.0, 0.0, 1.0, 10.0, 100.0][..],
+            max_relative = 1e-12,
+        );
+        check_idx(&grid);
+        check_idx(&Grid::symlog(-2.0, 5.0, 17, 0.03));
+    }
+
+    #[test]
+    fn asinh() {
+        let grid = Grid::asinh(-5.0, 5.0, 10, 0.1);
+        let borders = grid.get_borders();
+        for (a, b) in borders.iter().zip(borders.iter().rev()) {
+            assert_relative_eq!(*a, -*b, max_relative = 1e-12);
+        }
+        // Central cells are narrower
+        assert!(borders[6] - borders[5] < borders[10] - borders[9]);
+        check_idx(&grid);
+        check_idx(&Grid::asinh(-1.0, 4.0, 13, 0.5));
+    }
+
+    #[test]
+    fn transform() {
+        let linear = Grid::transform(-1.0, 2.0, 12, Transform::Linear);
+        assert_relative_eq!(
+            linear.get_borders().as_slice().unwrap(),
+            LinearGrid::new(-1.0, 2.0, 12)
+                .get_borders()
+                .as_slice()
+                .unwrap(),
+            max_relative = 1e-12,
+        );
+        let lg = Grid::transform(0.1, 100.0, 12, Transform::Lg { offset: 0.0 });
+        assert_relative_eq!(
+            lg.get_borders().as_slice().unwrap(),
+            LgGrid::from_start_end(0.1, 100.0, 12)
+                .get_borders()
+                .as_slice()
+                .unwrap(),
+            max_relative = 1e-12,
+        );
+        let sqrt = Grid::transform(0.0, 100.0, 10, Transform::Sqrt);
+        assert_relative_eq!(sqrt.get_borders()[1], 1.0, max_relative = 1e-12);
+
+        for grid in [
+            linear,
+            lg,
+            sqrt,
+            Grid::transform(-1.0, 4.0, 13, Transform::Asinh { scale: 0.5 }),
+            Grid::transform(0.0, 100.0, 10, Transform::Lg { offset: 1.0 }),
+            Grid::transform(-3.0, 2.0, 11, Transform::Power { exponent: 0.3 }),
+            Grid::transform(
+                0.0,
+                10.0,
+                7,
+                Transform::Custom {
+                    forward: f64::ln_1p,
+                    inverse: f64::exp_m1,
+                },
+            ),
+        ] {
+            check_idx(&grid);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn transform_infinite_start() {
+        Grid::transform(0.0, 100.0, 10, Transform::Lg { offset: 0.0 });
+    }
+
+    #[test]
+    fn quantiles() {
+        let t: Vec<_> = (0..200).map(|i| f64::powi(i as f64, 2) / 100.0).collect();
+        let grid = ArrayGrid::from_dt_quantiles(&[&t], 16, QuantileGridOptions::default()).unwrap();
+        assert_eq!(grid.cell_count(), 16);
+        let dmdt = DmDt::from_grids(grid, Grid::linear(-1.0, 1.0, 1));
+        let dt_points = dmdt.dt_points(&t);
+        let n_pairs = 200 * 199 / 2;
+        assert_eq!(dt_points.sum(), n_pairs);
+        assert!(dt_points.iter().all(|&x| x.abs_diff(n_pairs / 16) <= 8));
+
+        // Population of two light curves
+        let m1 = [0.0, 1.0, 3.0];
+        let m2 = [0.0, -1.0];
+        let grid =
+            ArrayGrid::from_dm_quantiles(&[&m1, &m2], 4, QuantileGridOptions::default()).unwrap();
+        let borders = grid.get_borders();
+        assert_eq!(
+            borders.slice(s![..4]).as_slice().unwrap(),
+            &[-1.0, 0.5, 1.5, 2.25][..]
+        );
+        assert!(borders[4] > 3.0);
+        assert_relative_eq!(borders[4], 3.0, max_relative = 1e-12);
+    }
+
+    #[test]
+    fn quantiles_ties_at_max() {
+        let values = [0.0, 0.0, 1.0, 1.0, 1.0, 1.0];
+        let grid = ArrayGrid::from_quantiles(&values, 3, QuantileGridOptions::default()).unwrap();
+        assert_eq!(grid.cell_count(), 2);
+        let mut counts = [0; 2];
+        for &x in values.iter() {
+            match grid.idx(x) {
+                CellIndex::Value(i) => counts[i] += 1,
+                _ => panic!("x = {x} is out of the grid"),
+            }
+        }
+        assert_eq!(counts, [2, 4]);
+    }
+
+    #[test]
+    fn quantiles_merging() {
+        let values = [0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 2.0, 3.0, 10.0];
+        let grid = ArrayGrid::from_quantiles(&values, 4, QuantileGridOptions::default()).unwrap();
+        let borders = grid.get_borders();
+        assert_eq!(borders.slice(s![..2]).as_slice().unwrap(), &[0.0, 2.0][..]);
+        assert_relative_eq!(borders[2], 10.0, max_relative = 1e-12);
+        assert!(matches!(
+            ArrayGrid::from_quantiles(
+                &values,
+                4,
+                QuantileGridOptions {
+                    min_width: 0.0,
+                    merge_duplicates: false,
+                }
+            ),
+            Err(ArrayGridError::ArrayIsNotAscending)
+        ));
+
+        let values: Vec<_> = (0..=100).map(|i| f64::powi(i as f64 / 10.0, 3)).collect();
+        let options = QuantileGridOptions {
+            min_width: 50.0,
+            merge_duplicates: true,
+        };
+        let grid = ArrayGrid::from_quantiles(&values, 20, options).unwrap();
+        let borders = grid.get_borders();
+        assert_eq!(borders[0], 0.0);
+        assert_relative_eq!(borders[borders.len() - 1], 1000.0, max_relative = 1e-12);
+        assert!(borders.windows(2).into_iter().all(|w| w[1] - w[0] >= 50.0));
+
+        assert!(matches!(
+            ArrayGrid::from_quantiles(&[1.0, 1.0], 4, QuantileGridOptions::default()),
+            Err(ArrayGridError::QuantileGridHasNoCells)
+        ));
+    }
+}
@@ -0,0 +1,389 @@
+use crate::{DmDt, ErfFloat, ErrorFunction};
+
+use ndarray::Array2;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+/// Smearing kernel trait
+///
+/// Kernel is a location-scale family of continuous distributions used to smear each pair of
+/// observations along dm axis, the scale `sigma` is the dm uncertainty. The kernel is integrated
+/// over each cell using its cumulative distribution function, support bounds limit the range of
+/// cells to integrate over.
+pub trait SmearingKernel<T>: Clone + Debug
+where
+    T: ErfFloat,
+{
+    /// Cumulative distribution function of the kernel located at `mean` with scale `sigma`
+    fn cdf(&self, x: T, mean: T, sigma: T) -> T;
+
+    /// Distance from the location to the right, starting from which [SmearingKernel::cdf()] is
+    /// unity within the float precision
+    fn max_dx_nonunity_cdf(&self, sigma: T) -> T;
+
+    /// Distance from the location to the left, up to which [SmearingKernel::cdf()] is zero within
+    /// the float precision
+    fn min_dx_nonzero_cdf(&self, sigma: T) -> T {
+        -self.max_dx_nonunity_cdf(sigma)
+    }
+}
+
+/// Gaussian kernel, `sigma` is its standard deviation
+///
+/// It is what [DmDt::gausses()] and [DmDt::cond_prob()] use, `Erf` is the [ErrorFunction] to
+/// compute the cumulative distribution function with.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GaussianKernel<Erf> {
+    erf: PhantomData<Erf>,
+}
+
+impl<Erf> GaussianKernel<Erf> {
+    /// Create new kernel, it is stateless and equivalent to [GaussianKernel::default()]
+    pub fn new() -> Self {
+        Self { erf: PhantomData }
+    }
+}
+
+impl<T, Erf> SmearingKernel<T> for GaussianKernel<Erf>
+where
+    T: ErfFloat,
+    Erf: ErrorFunction<T>,
+{
+    fn cdf(&self, x: T, mean: T, sigma: T) -> T {
+        Erf::normal_cdf(x, mean, sigma)
+    }
+
+    fn max_dx_nonunity_cdf(&self, sigma: T) -> T {
+        Erf::max_dx_nonunity_normal_cdf(sigma)
+    }
+
+    fn min_dx_nonzero_cdf(&self, sigma: T) -> T {
+        Erf::min_dx_nonzero_normal_cdf(sigma)
+    }
+}
+
+/// Student's t-distribution kernel, `sigma` is its scale parameter
+///
+/// It has heavier tails than the Gaussian for any number of degrees of freedom `nu`, which
+/// makes it robust to outliers. The variance is `sigma^2 nu / (nu - 2)` for `nu > 2` and
+/// infinite otherwise. Support bounds come from the power-law tail estimate, so they grow fast
+/// for small `nu` and the smearing could cover the whole grid.
+#[derive(Clone, Copy, Debug)]
+pub struct StudentTKernel<T> {
+    nu: T,
+    max_dx_over_sigma: T,
+}
+
+impl<T> StudentTKernel<T>
+where
+    T: ErfFloat,
+{
+    /// Create new kernel with `nu > 0` degrees of freedom
+    ///
+    /// # Panics
+    /// Panics if `nu` is not positive
+    pub fn new(nu: T) -> Self {
+        assert!(nu > T::zero());
+        // Tail probability is less than c nu^((nu-1)/2) x^(-nu), where c is the density
+        // normalisation, so it is less than epsilon starting from the value computed below. It is
+        // computed in double precision, because the log-gamma terms cancel each other at large nu
+        let nu64 = nu.to_f64().unwrap();
+        let ln_c = -ln_beta(0.5 * nu64, 0.5) - 0.5 * nu64.ln();
+        let ln_max_dx =
+            (ln_c + 0.5 * (nu64 - 1.0) * nu64.ln() - T::epsilon().to_f64().unwrap().ln()) / nu64;
+        let max_dx_over_sigma = ln_max_dx.exp().min(T::max_value().to_f64().unwrap());
+        Self {
+            nu,
+            max_dx_over_sigma: T::from(max_dx_over_sigma).unwrap(),
+        }
+    }
+
+    /// Number of degrees of freedom
+    pub fn nu(&self) -> T {
+        self.nu
+    }
+}
+
+impl<T> SmearingKernel<T> for StudentTKernel<T>
+where
+    T: ErfFloat,
+{
+    fn cdf(&self, x: T, mean: T, sigma: T) -> T {
+        let t = (x - mean) / sigma;
+        let tail = T::half()
+            * regularized_incomplete_beta(
+                T::half() * self.nu,
+                T::half(),
+                self.nu / (self.nu + t * t),
+            );
+        if t > T::zero() { T::one() - tail } else { tail }
+    }
+
+    fn max_dx_nonunity_cdf(&self, sigma: T) -> T {
+        self.max_dx_over_sigma * sigma
+    }
+}
+
+/// Laplace (double exponential) kernel, `sigma` is its standard deviation
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LaplaceKernel;
+
+impl<T> SmearingKernel<T> for LaplaceKernel
+where
+    T: ErfFloat,
+{
+    fn cdf(&self, x: T, mean: T, sigma: T) -> T {
+        let z = (x - mean) * T::SQRT_2() / sigma;
+        if z < T::zero() {
+            T::half() * z.exp()
+        } else {
+            T::one() - T::half() * (-z).exp()
+        }
+    }
+
+    fn max_dx_nonunity_cdf(&self, sigma: T) -> T {
+        sigma * T::FRAC_1_SQRT_2() * (T::half() / T::epsilon()).ln()
+    }
+}
+
+/// Uniform (top-hat) kernel, `sigma` is its standard deviation
+///
+/// The distribution is uniform on `[mean - sqrt(3) sigma, mean + sqrt(3) sigma]`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UniformKernel;
+
+impl UniformKernel {
+    fn half_width<T>(sigma: T) -> T
+    where
+        T: ErfFloat,
+    {
+        T::value_from(3_usize).unwrap().sqrt() * sigma
+    }
+}
+
+impl<T> SmearingKernel<T> for UniformKernel
+where
+    T: ErfFloat,
+{
+    fn cdf(&self, x: T, mean: T, sigma: T) -> T {
+        let half_width = Self::half_width(sigma);
+        (T::half() * (x - mean + half_width) / half_width).clamp(T::zero(), T::one())
+    }
+
+    fn max_dx_nonunity_cdf(&self, sigma: T) -> T {
+        Self::half_width(sigma)
+    }
+}
+
+/// Natural logarithm of the beta function `B(a, b)`
+///
+/// It is always computed in double precision, because the log-gamma terms cancel each other for
+/// large arguments.
+fn ln_beta(a: f64, b: f64) -> f64 {
+    libm::lgamma(a) + libm::lgamma(b) - libm::lgamma(a + b)
+}
+
+/// Regularized incomplete beta function `I_x(a, b)`
+///
+/// It uses continued fraction representation, see Numerical Recipes, section 6.4.
+fn regularized_incomplete_beta<T>(a: T, b: T, x: T) -> T
+where
+    T: ErfFloat,
+{
+    if x <= T::zero() {
+        return T::zero();
+    }
+    if x >= T::one() {
+        return T::one();
+    }
+    // Large terms of the logarithm cancel each other, so it is computed in double precision
+    let (a64, b64, x64) = (
+        a.to_f64().unwrap(),
+        b.to_f64().unwrap(),
+        x.to_f64().unwrap(),
+    );
+    let ln_front = a64 * x64.ln() + b64 * (-x64).ln_1p() - ln_beta(a64, b64);
+    let front = T::from(ln_front.exp()).unwrap();
+    // Continued fraction converges fast for x < (a + 1) / (a + b + 2), use symmetry otherwise
+    if x * (a + b + T::one() + T::one()) < a + T::one() {
+        front * incomplete_beta_continued_fraction(a, b, x) / a
+    } else {
+        T::one() - front * incomplete_beta_continued_fraction(b, a, T::one() - x) / b
+    }
+}
+
+/// Continued fraction for [regularized_incomplete_beta()] evaluated by modified Lentz's method
+fn incomplete_beta_continued_fraction<T>(a: T, b: T, x: T) -> T
+where
+    T: ErfFloat,
+{
+    const MAX_ITERATIONS: usize = 300;
+
+    let tiny = T::min_positive_value() / T::epsilon();
+    let not_tiny = |x: T| if x.abs() < tiny { tiny } else { x };
+
+    let qab = a + b;
+    let qap = a + T::one();
+    let qam = a - T::one();
+    let mut c = T::one();
+    let mut d = not_tiny(T::one() - qab * x / qap).recip();
+    let mut h = d;
+    for m in 1..=MAX_ITERATIONS {
+        let m = T::value_from(m).unwrap();
+        let m2 = m + m;
+
+        let aa = m * (b - m) * x / ((qam + m2) * (a + m2));
+        d = not_tiny(T::one() + aa * d).recip();
+        c = not_tiny(T::one() + aa / c);
+        h *= d * c;
+
+        let aa = -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2));
+        d = not_tiny(T::one() + aa * d).recip();
+        c = not_tiny(T::one() + aa / c);
+        let delta = d * c;
+        h *= delta;
+
+        if (delta - T::one()).abs() < T::epsilon() {
+            break;
+        }
+    }
+    h
+}
+
+impl<T> DmDt<T>
+where
+    T: ErfFloat,
+{
+    /// Represents each pair of (t, m, err2) points as a `kernel` distribution in dm-dt map
+    ///
+    /// `t` must be an ascending slice. It is a generalisation of [DmDt::gausses()], which is the
+    /// same as this method with [GaussianKernel]: each pair is smeared along dm axis with the
+    /// kernel located at `m2 - m1` with scale `sqrt(err2_1 + err2_2)`.
+    pub fn kernel_gausses<K>(&self, t: &[T], m: &[T], err2: &[T], kernel: &K) -> Array2<T>
+    where
+        K: SmearingKernel<T>,
+    {
+        let mut a = Array2::zeros(self.shape());
+        self.add_kernel_gausses(&mut a, t, m, err2, kernel);
+        a
+    }
+
+    /// Conditional probability `p(m2-m1|t2-t1)` with the given smearing kernel
+    ///
+    /// It is a generalisation of [DmDt::cond_prob()]: [DmDt::kernel_gausses()] normalized by
+    /// [DmDt::dt_points()].
+    pub fn kernel_cond_prob<K>(&self, t: &[T], m: &[T], err2: &[T], kernel: &K) -> Array2<T>
+    where
+        K: SmearingKernel<T>,
+    {
+        let mut a = Array2::zeros(self.shape());
+        self.fill_kernel_cond_prob(&mut a, t, m, err2, kernel);
+        a
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::{Eps1Over1e3Erf, ExactErf};
+    use crate::{Grid, LinearGrid};
+
+    use approx::assert_abs_diff_eq;
+    use ndarray::Array1;
+
+    #[test]
+    fn gaussian_kernel_vs_gausses() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f32, 2.0_f32, 32, 1.25_f32, 32);
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let m = t.mapv(f32::sin);
+        let err2 = Array1::from_elem(101, 0.001);
+        let (t, m, err2) = (
+            t.as_slice().unwrap(),
+            m.as_slice().unwrap(),
+            err2.as_slice().unwrap(),
+        );
+
+        let kernel = GaussianKernel::<Eps1Over1e3Erf>::new();
+        assert_eq!(
+            dmdt.kernel_gausses(t, m, err2, &kernel),
+            dmdt.gausses::<Eps1Over1e3Erf>(t, m, err2)
+        );
+        assert_eq!(
+            dmdt.kernel_cond_prob(t, m, err2, &kernel),
+            dmdt.cond_prob::<Eps1Over1e3Erf>(t, m, err2)
+        );
+    }
+
+    #[test]
+    fn student_t_cdf() {
+        let x = Array1::linspace(-20.0, 20.0, 401);
+
+        // Cauchy distribution
+        let kernel = StudentTKernel::new(1.0_f64);
+        for &x in x.iter() {
+            assert_abs_diff_eq!(
+                kernel.cdf(x, 1.0, 2.0),
+                0.5 + f64::atan((x - 1.0) / 2.0) / std::f64::consts::PI,
+                epsilon = 1e-12,
+            );
+        }
+
+        let kernel = StudentTKernel::new(2.0_f64);
+        for &x in x.iter() {
+            assert_abs_diff_eq!(
+                kernel.cdf(x, 0.0, 1.0),
+                0.5 + x / (2.0 * f64::sqrt(2.0 + x * x)),
+                epsilon = 1e-12,
+            );
+        }
+
+        // Large nu gives Gaussian
+        let kernel = StudentTKernel::new(1e6_f64);
+        for &x in x.iter() {
+            assert_abs_diff_eq!(
+                kernel.cdf(x, 0.0, 3.0),
+                ExactErf::normal_cdf(x, 0.0, 3.0),
+                epsilon = 1e-6,
+            );
+        }
+        // Single precision agrees with double precision for large nu
+        let kernel_f32 = StudentTKernel::new(1e4_f32);
+        let kernel_f64 = StudentTKernel::new(1e4_f64);
+        for &x in x.iter() {
+            assert_abs_diff_eq!(
+                kernel_f32.cdf(x as f32, 0.0, 3.0) as f64,
+                kernel_f64.cdf(x, 0.0, 3.0),
+                epsilon = 1e-3,
+            );
+        }
+    }
+
+    #[test]
+    fn support_bounds_and_normalisation() {
+        fn check<K: SmearingKernel<f64>>(kernel: K) {
+            let sigma = 0.1;
+            let mean = 0.3;
+            assert_abs_diff_eq!(
+                kernel.cdf(mean + kernel.max_dx_nonunity_cdf(sigma), mean, sigma),
+                1.0,
+                epsilon = 1e-15,
+            );
+            assert_abs_diff_eq!(
+                kernel.cdf(mean + kernel.min_dx_nonzero_cdf(sigma), mean, sigma),
+                0.0,
+                epsilon = 1e-15,
+            );
+            assert_abs_diff_eq!(kernel.cdf(mean, mean, sigma), 0.5, epsilon = 1e-15);
+
+            let grid: Grid<f64> = LinearGrid::new(-1e3, 1e3, 20000).into();
+            let (_idx, values) = DmDt::kernel_cell_integrals(&grid, mean, sigma, &kernel).unwrap();
+            assert_abs_diff_eq!(values.sum::<f64>(), 1.0, epsilon = 1e-6);
+        }
+
+        check(GaussianKernel::<ExactErf>::new());
+        check(StudentTKernel::new(3.0));
+        check(LaplaceKernel);
+        check(UniformKernel);
+    }
+}
@@ -3,21 +3,63 @@ cfg_attr(all(),
 doc = ::embed_doc_image::embed_image!("example_png", "example.png")))]
 #![doc = include_str!("../README.md")]
 
+pub use crate::accumulator::*;
 pub use crate::bayesian_blocks::*;
+pub use crate::cross_band::*;
+pub use crate::distance::*;
 pub use crate::dmdt::*;
 pub use crate::erf::*;
 pub use crate::float_trait::Float;
+pub use crate::flux::*;
 pub use crate::grid::*;
 #[cfg(feature = "png")]
 pub use crate::images::{png, to_png};
+pub use crate::kernel::*;
+pub use crate::map::*;
+pub use crate::norm::*;
+pub use crate::output::*;
+pub use crate::overflow::*;
+pub use crate::partial::*;
+pub use crate::quantiles::*;
+#[cfg(feature = "rand")]
+pub use crate::resampling::*;
+#[cfg(feature = "rand")]
+pub use crate::sampling::*;
+pub use crate::structure_function::*;
+pub use crate::upper_limits::*;
+pub use crate::validation::*;
 
+pub use enumflags2;
 pub use ndarray;
+#[cfg(feature = "rand")]
+pub use rand;
 
+mod accumulator;
+mod asymmetric;
+mod batch;
 mod bayesian_blocks;
+mod cross_band;
+mod distance;
 mod dmdt;
 mod erf;
 mod float_trait;
+mod flux;
 mod grid;
 #[cfg(feature = "png")]
 mod images;
+mod kernel;
+mod map;
+mod norm;
+mod output;
+mod overflow;
+mod partial;
+mod quantiles;
+mod rebin;
+#[cfg(feature = "rand")]
+mod resampling;
+#[cfg(feature = "rand")]
+mod sampling;
+mod structure_function;
+mod upper_limits;
 mod util;
+mod validation;
@@ -0,0 +1,289 @@
+use crate::{
+    DmDt, DmDtCompatibilityError, DmDtMapKind, DmDtNorm, DmDtNormError, DmDtShapeError, ErfFloat,
+    ErrorFunction, Float, Grid,
+};
+
+use enumflags2::BitFlags;
+use ndarray::{Array1, Array2, Zip};
+use num_traits::{Num, NumCast};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// dm–dt map bundled with its grids and metadata
+///
+/// Unlike bare arrays returned by [DmDt::points()] and similar methods, it keeps everything
+/// needed to interpret the map, so it can be archived and reloaded. Use [DmDt::points_map()],
+/// [DmDt::gausses_map()] or [DmDt::cond_prob_map()] to create it.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DmDtMap<T, V>
+where
+    T: Float,
+{
+    /// Map values, the shape is (N dt, N dm)
+    pub map: Array2<V>,
+    pub dt_grid: Grid<T>,
+    pub dm_grid: Grid<T>,
+    /// [DmDt] method produced the map
+    pub kind: DmDtMapKind,
+    /// Normalisation applied on top of the one implied by `kind`
+    pub norm: BitFlags<DmDtNorm>,
+    /// Number of pairs in each dt cell, see [DmDt::dt_points()]
+    pub dt_points: Array1<u64>,
+}
+
+/// Error type for [DmDtMap::merge()] and [DmDtMap::normalise()]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DmDtMapError {
+    #[error("incompatible grids: {0}")]
+    Incompatible(#[from] DmDtCompatibilityError),
+    #[error("shape error: {0}")]
+    Shape(#[from] DmDtShapeError),
+    #[error("map kinds {0:?} and {1:?} are not the same")]
+    KindMismatch(DmDtMapKind, DmDtMapKind),
+    #[error("normalised maps cannot be merged")]
+    Normalised,
+    #[error("map is already normalised by dt_points")]
+    DtPointsNormalised,
+    #[error("normalisation error: {0}")]
+    Norm(#[from] DmDtNormError),
+}
+
+impl<T, V> DmDtMap<T, V>
+where
+    T: Float,
+{
+    /// [DmDt] object having the grids of the map
+    pub fn dmdt(&self) -> DmDt<T> {
+        DmDt {
+            dt_grid: self.dt_grid.clone(),
+            dm_grid: self.dm_grid.clone(),
+        }
+    }
+
+    /// Total number of pairs having dt inside the dt grid, including pairs having dm out of the
+    /// dm grid
+    pub fn n_pairs(&self) -> u64 {
+        self.dt_points.sum()
+    }
+
+    /// Checks if `map` and `dt_points` shapes match the grids, e.g. after deserialisation
+    pub fn check_shape(&self) -> Result<(), DmDtShapeError> {
+        let dmdt = self.dmdt();
+        dmdt.check_map_shape(&self.map)?;
+        dmdt.check_dt_points_shape(&self.dt_points)
+    }
+}
+
+impl<T, V> DmDtMap<T, V>
+where
+    T: Float,
+    V: Num + NumCast + Copy,
+{
+    /// Merges other map into this one, so the result is the map of the union of pairs
+    ///
+    /// Maps must have the same grids, see [DmDt::check_compatible()], and the same kind, and
+    /// must not be normalised with [DmDtNorm].
+    /// [DmDtMapKind::Points] and [DmDtMapKind::Gausses] maps are summed, rows of
+    /// [DmDtMapKind::CondProb] maps are averaged with `dt_points` weights.
+    pub fn merge(&mut self, other: &Self) -> Result<(), DmDtMapError> {
+        self.dmdt().check_compatible(&other.dmdt())?;
+        self.check_shape()?;
+        other.check_shape()?;
+        if self.kind != other.kind {
+            return Err(DmDtMapError::KindMismatch(self.kind, other.kind));
+        }
+        if !self.norm.is_empty() || !other.norm.is_empty() {
+            return Err(DmDtMapError::Normalised);
+        }
+
+        match self.kind {
+            DmDtMapKind::Points | DmDtMapKind::Gausses => {
+                self.map.zip_mut_with(&other.map, |x, &y| *x = *x + y)
+            }
+            DmDtMapKind::CondProb => Zip::from(self.map.rows_mut())
+                .and(other.map.rows())
+                .and(&self.dt_points)
+                .and(&other.dt_points)
+                .for_each(|mut row, other_row, &n, &other_n| {
+                    if other_n == 0 {
+                        return;
+                    }
+                    let n: V = NumCast::from(n).unwrap();
+                    let other_n: V = NumCast::from(other_n).unwrap();
+                    row.zip_mut_with(&other_row, |x, &y| {
+                        *x = (*x * n + y * other_n) / (n + other_n)
+                    });
+                }),
+        }
+        self.dt_points += &other.dt_points;
+        Ok(())
+    }
+}
+
+impl<T> DmDtMap<T, T>
+where
+    T: Float,
+{
+    /// Normalise the map in place and record applied normalisation, see [DmDt::normalise()]
+    ///
+    /// [DmDtNorm::DtPoints] cannot be applied to [DmDtMapKind::CondProb] maps and to maps
+    /// already normalised with it, because their rows are already divided by `dt_points`.
+    pub fn normalise(&mut self, norm: impl Into<BitFlags<DmDtNorm>>) -> Result<(), DmDtMapError> {
+        let norm = norm.into();
+        if norm.contains(DmDtNorm::DtPoints)
+            && (self.kind == DmDtMapKind::CondProb || self.norm.contains(DmDtNorm::DtPoints))
+        {
+            return Err(DmDtMapError::DtPointsNormalised);
+        }
+        self.check_shape()?;
+        self.dmdt()
+            .normalise(&mut self.map, norm, Some(&self.dt_points))?;
+        self.norm |= norm;
+        Ok(())
+    }
+}
+
+impl<T> DmDt<T>
+where
+    T: Float,
+{
+    fn map_with_metadata<V>(&self, map: Array2<V>, kind: DmDtMapKind, t: &[T]) -> DmDtMap<T, V> {
+        DmDtMap {
+            map,
+            dt_grid: self.dt_grid.clone(),
+            dm_grid: self.dm_grid.clone(),
+            kind,
+            norm: BitFlags::empty(),
+            dt_points: self.dt_points(t),
+        }
+    }
+
+    /// [DmDt::points()] bundled into [DmDtMap]
+    pub fn points_map(&self, t: &[T], m: &[T]) -> DmDtMap<T, u64> {
+        self.map_with_metadata(self.points(t, m), DmDtMapKind::Points, t)
+    }
+
+    /// [DmDt::gausses()] bundled into [DmDtMap]
+    pub fn gausses_map<Erf>(&self, t: &[T], m: &[T], err2: &[T]) -> DmDtMap<T, T>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        self.map_with_metadata(self.gausses::<Erf>(t, m, err2), DmDtMapKind::Gausses, t)
+    }
+
+    /// [DmDt::cond_prob()] bundled into [DmDtMap]
+    pub fn cond_prob_map<Erf>(&self, t: &[T], m: &[T], err2: &[T]) -> DmDtMap<T, T>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        self.map_with_metadata(self.cond_prob::<Erf>(t, m, err2), DmDtMapKind::CondProb, t)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::ExactErf;
+
+    use approx::assert_abs_diff_eq;
+    use ndarray::s;
+
+    fn light_curve(n: usize, shift: f64) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        let t: Vec<_> = (0..n).map(|i| i as f64 + shift).collect();
+        let m = t.iter().map(|&x| f64::sin(0.3 * x)).collect();
+        let err2 = vec![0.01; n];
+        (t, m, err2)
+    }
+
+    #[test]
+    fn merge() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 16, 1.25_f64, 16);
+        let (t1, m1, err2_1) = light_curve(101, 0.0);
+        let (t2, m2, err2_2) = light_curve(31, 0.5);
+
+        let mut points = dmdt.points_map(&t1, &m1);
+        points.merge(&dmdt.points_map(&t2, &m2)).unwrap();
+        assert_eq!(points.map, dmdt.points(&t1, &m1) + dmdt.points(&t2, &m2));
+        // The only pair out of the dt grid is the first and the last points of the first curve
+        assert_eq!(points.n_pairs(), 100 * 101 / 2 - 1 + 30 * 31 / 2);
+
+        let mut gausses = dmdt.gausses_map::<ExactErf>(&t1, &m1, &err2_1);
+        gausses
+            .merge(&dmdt.gausses_map::<ExactErf>(&t2, &m2, &err2_2))
+            .unwrap();
+
+        let mut cond_prob = dmdt.cond_prob_map::<ExactErf>(&t1, &m1, &err2_1);
+        cond_prob
+            .merge(&dmdt.cond_prob_map::<ExactErf>(&t2, &m2, &err2_2))
+            .unwrap();
+        assert_eq!(cond_prob.dt_points, gausses.dt_points);
+        // Conditional probability of the union is the sum of Gaussians normalised by dt_points
+        gausses.normalise(DmDtNorm::DtPoints).unwrap();
+        assert_eq!(gausses.norm, DmDtNorm::DtPoints);
+        assert_abs_diff_eq!(
+            cond_prob.map.as_slice().unwrap(),
+            gausses.map.as_slice().unwrap(),
+            epsilon = 1e-12,
+        );
+    }
+
+    #[test]
+    fn merge_errors() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 16, 1.25_f64, 16);
+        let (t, m, err2) = light_curve(11, 0.0);
+
+        let mut gausses = dmdt.gausses_map::<ExactErf>(&t, &m, &err2);
+        let other_grid = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 16, 1.5_f64, 16);
+        assert_eq!(
+            gausses.merge(&other_grid.gausses_map::<ExactErf>(&t, &m, &err2)),
+            Err(DmDtCompatibilityError::DmGridMismatch.into())
+        );
+        assert_eq!(
+            gausses.merge(&dmdt.cond_prob_map::<ExactErf>(&t, &m, &err2)),
+            Err(DmDtMapError::KindMismatch(
+                DmDtMapKind::Gausses,
+                DmDtMapKind::CondProb
+            ))
+        );
+        let other = gausses.clone();
+        gausses.normalise(DmDtNorm::Max).unwrap();
+        assert_eq!(gausses.merge(&other), Err(DmDtMapError::Normalised));
+
+        let mut truncated = other.clone();
+        truncated.dt_points = truncated.dt_points.slice_move(s![1..]);
+        assert_eq!(
+            truncated.clone().merge(&other),
+            Err(DmDtShapeError::DtPointsShapeMismatch(15, 16).into())
+        );
+        assert_eq!(
+            other.clone().merge(&truncated),
+            Err(DmDtShapeError::DtPointsShapeMismatch(15, 16).into())
+        );
+    }
+
+    #[test]
+    fn normalise_errors() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 16, 1.25_f64, 16);
+        let (t, m, err2) = light_curve(11, 0.0);
+
+        let mut cond_prob = dmdt.cond_prob_map::<ExactErf>(&t, &m, &err2);
+        assert_eq!(
+            cond_prob.normalise(DmDtNorm::DtPoints),
+            Err(DmDtMapError::DtPointsNormalised)
+        );
+        cond_prob.normalise(DmDtNorm::Max).unwrap();
+
+        let mut gausses = dmdt.gausses_map::<ExactErf>(&t, &m, &err2);
+        gausses.normalise(DmDtNorm::DtPoints).unwrap();
+        assert_eq!(
+            gausses.normalise(DmDtNorm::DtPoints | DmDtNorm::Max),
+            Err(DmDtMapError::DtPointsNormalised)
+        );
+        assert_eq!(gausses.norm, DmDtNorm::DtPoints);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{DmDt, DmDtShapeError, Float, GridTrait};
+
+use enumflags2::{BitFlags, bitflags};
+use ndarray::{ArrayRef1, ArrayRef2, Axis, Zip};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Normalisation of dm–dt maps, see [DmDt::normalise()]
+///
+/// Flags could be combined into [BitFlags], the order of operations is the order of the
+/// variants:
+/// 1. [DmDtNorm::DtPoints] divides each dt row by the number of pairs in it,
+/// 2. [DmDtNorm::DtRow] divides each dt row by its sum,
+/// 3. [DmDtNorm::Density] divides each cell by its area,
+/// 4. [DmDtNorm::Sum] divides each cell by the total sum,
+/// 5. [DmDtNorm::Max] divides each cell by the overall maximum value.
+///
+/// Rows and maps having zero denominator are left unchanged.
+#[bitflags]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DmDtNorm {
+    /// Divide each dt row by the number of pairs having dt in the row, including pairs having dm
+    /// out of the dm grid, see [DmDt::dt_points()]
+    ///
+    /// It is the normalisation of [DmDt::cond_prob()] and it is what `--norm=lgdt` does in `dmdt`
+    /// executable.
+    DtPoints,
+    /// Divide each dt row by its sum, so each non-empty row sums to unity
+    DtRow,
+    /// Divide each cell by its area, i.e. the product of dt and dm cell sizes
+    Density,
+    /// Divide each cell by the total sum of the map
+    Sum,
+    /// Divide each cell by the overall maximum value of the map
+    Max,
+}
+
+/// Error type for [DmDt::normalise()]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DmDtNormError {
+    #[error("DmDtNorm::DtPoints normalisation requires dt_points")]
+    DtPointsRequired,
+    #[error("shape error: {0}")]
+    Shape(#[from] DmDtShapeError),
+}
+
+/// Divides each row of `a` by the corresponding non-zero value of `dt_points`
+pub(crate) fn normalise_by_dt_points<T>(a: &mut ArrayRef2<T>, dt_points: &ArrayRef1<u64>)
+where
+    T: Float,
+{
+    Zip::from(a.rows_mut())
+        .and(dt_points)
+        .for_each(|mut row, &count| {
+            if count == 0 {
+                return;
+            }
+            row /= T::approx_from(count).unwrap();
+        });
+}
+
+impl<T> DmDt<T>
+where
+    T: Float,
+{
+    /// Normalise dm–dt map in place
+    ///
+    /// `map` could be an output of any [DmDt] map method converted to float if needed. `norm` is a
+    /// combination of [DmDtNorm] flags, see its documentation for the order of operations.
+    /// `dt_points` is required for [DmDtNorm::DtPoints] only and ignored otherwise, it is usually
+    /// the output of [DmDt::dt_points()] for the same light curve.
+    pub fn normalise(
+        &self,
+        map: &mut ArrayRef2<T>,
+        norm: impl Into<BitFlags<DmDtNorm>>,
+        dt_points: Option<&ArrayRef1<u64>>,
+    ) -> Result<(), DmDtNormError> {
+        let norm = norm.into();
+        self.check_map_shape(map)?;
+
+        if norm.contains(DmDtNorm::DtPoints) {
+            let dt_points = dt_points.ok_or(DmDtNormError::DtPointsRequired)?;
+            self.check_dt_points_shape(dt_points)?;
+            normalise_by_dt_points(map, dt_points);
+        }
+        if norm.contains(DmDtNorm::DtRow) {
+            for mut row in map.axis_iter_mut(Axis(0)) {
+                let sum = row.sum();
+                if !sum.is_zero() {
+                    row /= sum;
+                }
+            }
+        }
+        if norm.contains(DmDtNorm::Density) {
+            let dt_sizes = self.dt_grid.get_borders();
+            let dt_sizes = &dt_sizes.slice(ndarray::s![1..]) - &dt_sizes.slice(ndarray::s![..-1]);
+            let dm_sizes = self.dm_grid.get_borders();
+            let dm_sizes = &dm_sizes.slice(ndarray::s![1..]) - &dm_sizes.slice(ndarray::s![..-1]);
+            Zip::from(map.rows_mut())
+                .and(&dt_sizes)
+                .for_each(|mut row, &dt_size| {
+                    row.zip_mut_with(&dm_sizes, |x, &dm_size| *x /= dt_size * dm_size);
+                });
+        }
+        if norm.contains(DmDtNorm::Sum) {
+            let sum = map.sum();
+            if !sum.is_zero() {
+                *map /= sum;
+            }
+        }
+        if norm.contains(DmDtNorm::Max) {
+            let max = map.iter().copied().fold(T::neg_infinity(), T::max);
+            if max.is_finite() && !max.is_zero() {
+                *map /= max;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::ExactErf;
+
+    use approx::assert_abs_diff_eq;
+    use ndarray::Array1;
+
+    #[test]
+    fn dt_points_vs_cond_prob() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 32, 1.25_f64, 32);
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let m = t.mapv(f64::sin);
+        let err2 = Array1::from_elem(101, 0.001);
+        let (t, m, err2) = (
+            t.as_slice().unwrap(),
+            m.as_slice().unwrap(),
+            err2.as_slice().unwrap(),
+        );
+
+        let mut map = dmdt.gausses::<ExactErf>(t, m, err2);
+        assert_eq!(
+            dmdt.normalise(&mut map, DmDtNorm::DtPoints, None),
+            Err(DmDtNormError::DtPointsRequired)
+        );
+        dmdt.normalise(&mut map, DmDtNorm::DtPoints, Some(&dmdt.dt_points(t)))
+            .unwrap();
+        assert_eq!(map, dmdt.cond_prob::<ExactErf>(t, m, err2));
+    }
+
+    #[test]
+    fn combined() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 32, 1.25_f64, 32);
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let m = t.mapv(f64::sin);
+
+        let points = dmdt.points(t.as_slice().unwrap(), m.as_slice().unwrap());
+
+        let mut map = points.mapv(|x| x as f64);
+        dmdt.normalise(&mut map, DmDtNorm::DtRow | DmDtNorm::Max, None)
+            .unwrap();
+        assert_abs_diff_eq!(map.iter().copied().fold(0.0, f64::max), 1.0);
+
+        let mut map = points.mapv(|x| x as f64);
+        dmdt.normalise(&mut map, DmDtNorm::Density | DmDtNorm::Sum, None)
+            .unwrap();
+        assert_abs_diff_eq!(map.sum(), 1.0, epsilon = 1e-12);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{DmDt, DmDtShapeError, ErfFloat, ErrorFunction, Float};
+
+use ndarray::{Array2, ArrayRef1, ArrayRef2, Zip};
+
+/// How `*_into` methods of [DmDt] treat existing values of the output array
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Replace existing values
+    #[default]
+    Overwrite,
+    /// Add new values to existing ones
+    Accumulate,
+}
+
+impl<T> DmDt<T>
+where
+    T: Float,
+{
+    /// [DmDt::points()] writing into the given array or array view
+    ///
+    /// `out` must have [DmDt::shape()] shape, it could be a slice of a larger array.
+    pub fn points_into(
+        &self,
+        out: &mut ArrayRef2<u64>,
+        t: &[T],
+        m: &[T],
+        mode: OutputMode,
+    ) -> Result<(), DmDtShapeError> {
+        self.check_map_shape(out)?;
+        if mode == OutputMode::Overwrite {
+            out.fill(0);
+        }
+        self.add_points(out, t, m);
+        Ok(())
+    }
+
+    /// [DmDt::gausses()] writing into the given array or array view
+    ///
+    /// `out` must have [DmDt::shape()] shape, it could be a slice of a larger array.
+    pub fn gausses_into<Erf>(
+        &self,
+        out: &mut ArrayRef2<T>,
+        t: &[T],
+        m: &[T],
+        err2: &[T],
+        mode: OutputMode,
+    ) -> Result<(), DmDtShapeError>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        self.check_map_shape(out)?;
+        if mode == OutputMode::Overwrite {
+            out.fill(T::zero());
+        }
+        self.add_gausses::<Erf>(out, t, m, err2);
+        Ok(())
+    }
+
+    /// [DmDt::dt_points()] writing into the given array or array view
+    ///
+    /// `out` length must be the number of dt grid cells.
+    pub fn dt_points_into(
+        &self,
+        out: &mut ArrayRef1<u64>,
+        t: &[T],
+        mode: OutputMode,
+    ) -> Result<(), DmDtShapeError> {
+        self.check_dt_points_shape(out)?;
+        if mode == OutputMode::Overwrite {
+            out.fill(0);
+        }
+        self.add_dt_points(out, t);
+        Ok(())
+    }
+
+    /// [DmDt::cond_prob()] writing into the given array or array view
+    ///
+    /// `out` must have [DmDt::shape()] shape, it could be a slice of a larger array.
+    /// [OutputMode::Accumulate] requires a temporary map, because the normalisation must not
+    /// affect existing values.
+    pub fn cond_prob_into<Erf>(
+        &self,
+        out: &mut ArrayRef2<T>,
+        t: &[T],
+        m: &[T],
+        err2: &[T],
+        mode: OutputMode,
+    ) -> Result<(), DmDtShapeError>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        self.check_map_shape(out)?;
+        match mode {
+            OutputMode::Overwrite => {
+                out.fill(T::zero());
+                self.fill_cond_prob::<Erf>(out, t, m, err2);
+            }
+            OutputMode::Accumulate => {
+                let mut a = Array2::zeros(self.shape());
+                self.fill_cond_prob::<Erf>(&mut a, t, m, err2);
+                Zip::from(out).and(&a).for_each(|x, &y| *x += y);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::ExactErf;
+
+    use ndarray::{Array1, Array3, Axis, s};
+
+    #[test]
+    fn into_slices() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 32, 1.25_f64, 32);
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let m = t.mapv(f64::sin);
+        let err2 = Array1::from_elem(101, 0.001);
+        let (t, m, err2) = (
+            t.as_slice().unwrap(),
+            m.as_slice().unwrap(),
+            err2.as_slice().unwrap(),
+        );
+
+        let mut points = Array3::from_elem((2, 32, 32), 7);
+        for mode in [OutputMode::Overwrite, OutputMode::Accumulate] {
+            dmdt.points_into(&mut points.index_axis_mut(Axis(0), 1), t, m, mode)
+                .unwrap();
+        }
+        assert_eq!(points.index_axis(Axis(0), 1), dmdt.points(t, m) * 2);
+        assert!(points.index_axis(Axis(0), 0).iter().all(|&x| x == 7));
+
+        let mut dt_points = Array1::ones(32);
+        dmdt.dt_points_into(&mut dt_points, t, OutputMode::Accumulate)
+            .unwrap();
+        assert_eq!(dt_points, dmdt.dt_points(t) + 1);
+
+        // Non-contiguous view
+        let mut gausses = Array2::from_elem((32, 64), f64::NAN);
+        dmdt.gausses_into::<ExactErf>(
+            &mut gausses.slice_mut(s![.., ..;2]),
+            t,
+            m,
+            err2,
+            OutputMode::Overwrite,
+        )
+        .unwrap();
+        assert_eq!(
+            gausses.slice(s![.., ..;2]),
+            dmdt.gausses::<ExactErf>(t, m, err2)
+        );
+
+        let mut cond_prob = Array2::ones((32, 32));
+        dmdt.cond_prob_into::<ExactErf>(&mut cond_prob, t, m, err2, OutputMode::Accumulate)
+            .unwrap();
+        assert_eq!(cond_prob, dmdt.cond_prob::<ExactErf>(t, m, err2) + 1.0);
+        dmdt.cond_prob_into::<ExactErf>(&mut cond_prob, t, m, err2, OutputMode::Overwrite)
+            .unwrap();
+        assert_eq!(cond_prob, dmdt.cond_prob::<ExactErf>(t, m, err2));
+    }
+
+    #[test]
+    fn shape_mismatch() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 32, 1.25_f64, 16);
+        let mut map = Array2::zeros((16, 32));
+        assert_eq!(
+            dmdt.points_into(&mut map, &[0.0], &[0.0], OutputMode::Overwrite),
+            Err(DmDtShapeError::MapShapeMismatch((16, 32), (32, 16)))
+        );
+        let mut dt_points = Array1::zeros(16);
+        assert_eq!(
+            dmdt.dt_points_into(&mut dt_points, &[0.0], OutputMode::Overwrite),
+            Err(DmDtShapeError::DtPointsShapeMismatch(16, 32))
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{CellIndex, DmDt, ErfFloat, ErrorFunction, Float, GridTrait};
+
+use ndarray::{Array1, Array2};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Amount of pairs fallen out of the dm–dt grid
+///
+/// For [DmDt::points_with_overflow()] `V` is [u64] and amounts are pair counts, for
+/// [DmDt::gausses_with_overflow()] `V` is float and amounts are integrals of the pair
+/// distributions out of the dm grid. For each dt row the sum of the map row, `dm_below` and
+/// `dm_above` is the number of pairs in the row, i.e. the denominator of [DmDt::cond_prob()],
+/// see [DmDt::dt_points()].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DmDtOverflow<V> {
+    /// Amount of pairs having dm less than the dm grid start, for each dt row
+    pub dm_below: Array1<V>,
+    /// Amount of pairs having dm greater or equal than the dm grid end, for each dt row
+    pub dm_above: Array1<V>,
+    /// Number of pairs having dt less than the dt grid start
+    pub dt_below: u64,
+    /// Number of pairs having dt greater or equal than the dt grid end
+    pub dt_above: u64,
+}
+
+impl<V> DmDtOverflow<V>
+where
+    V: Clone + num_traits::Zero,
+{
+    fn zeros(n_dt: usize) -> Self {
+        Self {
+            dm_below: Array1::zeros(n_dt),
+            dm_above: Array1::zeros(n_dt),
+            dt_below: 0,
+            dt_above: 0,
+        }
+    }
+}
+
+impl<T> DmDt<T>
+where
+    T: Float,
+{
+    /// Calls `f(overflow, idx_dt, i1, i2)` for each pair of points having dt inside the dt grid
+    /// and counts pairs out of it
+    ///
+    /// `t` must be an ascending slice
+    fn for_each_pair_with_overflow<V, F>(&self, t: &[T], overflow: &mut DmDtOverflow<V>, mut f: F)
+    where
+        F: FnMut(&mut DmDtOverflow<V>, usize, usize, usize),
+    {
+        let mut n_inside = 0;
+        self.for_each_pair(t, |idx_dt, i1, i2| {
+            n_inside += 1;
+            f(overflow, idx_dt, i1, i2);
+        });
+        // dt increases with the second point, so the pairs below the grid go first
+        let n_below: usize = t
+            .iter()
+            .enumerate()
+            .map(|(i1, &x1)| {
+                t[i1 + 1..]
+                    .partition_point(|&x2| matches!(self.dt_grid.idx(x2 - x1), CellIndex::LowerMin))
+            })
+            .sum();
+        let n_pairs = t.len() * t.len().saturating_sub(1) / 2;
+        overflow.dt_below += n_below as u64;
+        overflow.dt_above += (n_pairs - n_inside - n_below) as u64;
+    }
+
+    /// [DmDt::points()] with accounting of the pairs out of the grid
+    ///
+    /// `t` must be an ascending slice
+    pub fn points_with_overflow(&self, t: &[T], m: &[T]) -> (Array2<u64>, DmDtOverflow<u64>) {
+        let mut a = Array2::zeros(self.shape());
+        let mut overflow = DmDtOverflow::zeros(self.dt_grid.cell_count());
+        self.for_each_pair_with_overflow(t, &mut overflow, |overflow, idx_dt, i1, i2| {
+            match self.dm_grid.idx(m[i2] - m[i1]) {
+                CellIndex::LowerMin => overflow.dm_below[idx_dt] += 1,
+                CellIndex::GreaterMax => overflow.dm_above[idx_dt] += 1,
+                CellIndex::Value(idx_dm) => a[(idx_dt, idx_dm)] += 1,
+            }
+        });
+        (a, overflow)
+    }
+
+    /// [DmDt::gausses()] with accounting of the pairs out of the grid
+    ///
+    /// `t` must be an ascending slice. The mass out of the dm grid is computed with `Erf` as the
+    /// cumulative distribution at the grid borders.
+    pub fn gausses_with_overflow<Erf>(
+        &self,
+        t: &[T],
+        m: &[T],
+        err2: &[T],
+    ) -> (Array2<T>, DmDtOverflow<T>)
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        let mut a = Array2::zeros(self.shape());
+        let mut overflow = DmDtOverflow::zeros(self.dt_grid.cell_count());
+        let dm_start = self.dm_grid.get_start();
+        let dm_end = self.dm_grid.get_end();
+        self.for_each_pair_with_overflow(t, &mut overflow, |overflow, idx_dt, i1, i2| {
+            let dm = m[i2] - m[i1];
+            let dm_err = T::sqrt(err2[i1] + err2[i2]);
+            overflow.dm_below[idx_dt] += Erf::normal_cdf(dm_start, dm, dm_err);
+            overflow.dm_above[idx_dt] += T::one() - Erf::normal_cdf(dm_end, dm, dm_err);
+            self.add_weighted_gauss::<Erf>(&mut a, idx_dt, dm, dm_err, T::one());
+        });
+        (a, overflow)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::ExactErf;
+
+    use approx::assert_abs_diff_eq;
+    use ndarray::Axis;
+
+    #[test]
+    fn overflow_sums() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 1.5_f64, 32, 0.5_f64, 32);
+        let t = Array1::linspace(0.0, 100.0, 201);
+        let m = t.mapv(f64::sin);
+        let err2 = Array1::from_elem(201, 0.001);
+        let (t, m, err2) = (
+            t.as_slice().unwrap(),
+            m.as_slice().unwrap(),
+            err2.as_slice().unwrap(),
+        );
+        let dt_points = dmdt.dt_points(t);
+        let n_pairs = (t.len() * (t.len() - 1) / 2) as u64;
+
+        let (points, overflow) = dmdt.points_with_overflow(t, m);
+        assert_eq!(points, dmdt.points(t, m));
+        assert_eq!(
+            points.sum_axis(Axis(1)) + &overflow.dm_below + &overflow.dm_above,
+            dt_points
+        );
+        assert!(overflow.dm_below.sum() > 0);
+        assert!(overflow.dm_above.sum() > 0);
+        assert_eq!(overflow.dt_below, 200);
+        assert_eq!(
+            dt_points.sum() + overflow.dt_below + overflow.dt_above,
+            n_pairs
+        );
+
+        let (gausses, overflow) = dmdt.gausses_with_overflow::<ExactErf>(t, m, err2);
+        assert_eq!(gausses, dmdt.gausses::<ExactErf>(t, m, err2));
+        assert_abs_diff_eq!(
+            (gausses.sum_axis(Axis(1)) + &overflow.dm_below + &overflow.dm_above)
+                .as_slice()
+                .unwrap(),
+            dt_points.mapv(|x| x as f64).as_slice().unwrap(),
+            epsilon = 1e-9,
+        );
+        assert_eq!(
+            dt_points.sum() + overflow.dt_below + overflow.dt_above,
+            n_pairs
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use crate::cross_band::CrossPairs;
+use crate::norm::normalise_by_dt_points;
+use crate::{
+    CellIndex, DmDt, DmDtCompatibilityError, DmDtShapeError, ErfFloat, ErrorFunction, Float, Grid,
+    GridTrait,
+};
+
+use ndarray::{Array1, Array2};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, AddAssign};
+use thiserror::Error;
+
+/// Partial sums of dm–dt maps which could be combined exactly
+///
+/// Light curve could be split into chunks, e.g. seasons or nights, processed independently by
+/// [DmDt::partial()] and [DmDt::cross_partial()], and the results summed with `+` or `+=`. The sum
+/// of partials of all chunks and all pairs of chunks is the same as the partial of the whole
+/// light curve, up to floating point round-off. Use [DmDtPartial::cond_prob()] to get the
+/// conditional probability map from the sum. Partials keep their grids, so only partials of the
+/// same grids could be summed, see [DmDtPartial::merge()].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DmDtPartial<T>
+where
+    T: Float,
+{
+    pub dt_grid: Grid<T>,
+    pub dm_grid: Grid<T>,
+    /// Pair counts, see [DmDt::points()]
+    pub points: Array2<u64>,
+    /// Sum of pair Gaussians, see [DmDt::gausses()]
+    pub gausses: Array2<T>,
+    /// Number of pairs in each dt cell, see [DmDt::dt_points()]
+    pub dt_points: Array1<u64>,
+}
+
+impl<T> DmDtPartial<T>
+where
+    T: Float,
+{
+    /// Empty partial of the given grids, it is the neutral element of the summation
+    pub fn zeros(dmdt: &DmDt<T>) -> Self {
+        let shape = dmdt.shape();
+        Self {
+            dt_grid: dmdt.dt_grid.clone(),
+            dm_grid: dmdt.dm_grid.clone(),
+            points: Array2::zeros(shape),
+            gausses: Array2::zeros(shape),
+            dt_points: Array1::zeros(shape.0),
+        }
+    }
+
+    /// [DmDt] object having the grids of the partial
+    pub fn dmdt(&self) -> DmDt<T> {
+        DmDt {
+            dt_grid: self.dt_grid.clone(),
+            dm_grid: self.dm_grid.clone(),
+        }
+    }
+
+    /// Checks if the map and `dt_points` shapes match the grids, e.g. after deserialisation
+    pub fn check_shape(&self) -> Result<(), DmDtShapeError> {
+        let dmdt = self.dmdt();
+        dmdt.check_map_shape(&self.points)?;
+        dmdt.check_map_shape(&self.gausses)?;
+        dmdt.check_dt_points_shape(&self.dt_points)
+    }
+
+    /// Adds other partial to this one
+    ///
+    /// Partials must have the same grids, see [DmDt::check_compatible()], `+` and `+=` panic
+    /// otherwise.
+    pub fn merge(&mut self, other: &Self) -> Result<(), DmDtPartialError> {
+        self.dmdt().check_compatible(&other.dmdt())?;
+        self.check_shape()?;
+        other.check_shape()?;
+        self.points += &other.points;
+        self.gausses += &other.gausses;
+        self.dt_points += &other.dt_points;
+        Ok(())
+    }
+
+    /// Conditional probability map, see [DmDt::cond_prob()]
+    pub fn cond_prob(&self) -> Array2<T> {
+        let mut a = self.gausses.clone();
+        normalise_by_dt_points(&mut a, &self.dt_points);
+        a
+    }
+}
+
+/// Error type for [DmDtPartial::merge()]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DmDtPartialError {
+    #[error("incompatible grids: {0}")]
+    Incompatible(#[from] DmDtCompatibilityError),
+    #[error("shape error: {0}")]
+    Shape(#[from] DmDtShapeError),
+}
+
+impl<T> AddAssign<&DmDtPartial<T>> for DmDtPartial<T>
+where
+    T: Float,
+{
+    /// # Panics
+    /// Panics if the partials cannot be merged, see [DmDtPartial::merge()]
+    fn add_assign(&mut self, rhs: &DmDtPartial<T>) {
+        if let Err(err) = self.merge(rhs) {
+            panic!("{err}");
+        }
+    }
+}
+
+impl<T> AddAssign for DmDtPartial<T>
+where
+    T: Float,
+{
+    fn add_assign(&mut self, rhs: Self) {
+        *self += &rhs;
+    }
+}
+
+impl<T> Add for DmDtPartial<T>
+where
+    T: Float,
+{
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        self += &rhs;
+        self
+    }
+}
+
+impl<T> DmDt<T>
+where
+    T: Float,
+{
+    /// Partial sums of a light curve chunk, see [DmDtPartial]
+    ///
+    /// `t` must be an ascending slice
+    pub fn partial<Erf>(&self, t: &[T], m: &[T], err2: &[T]) -> DmDtPartial<T>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        DmDtPartial {
+            dt_grid: self.dt_grid.clone(),
+            dm_grid: self.dm_grid.clone(),
+            points: self.points(t, m),
+            gausses: self.gausses::<Erf>(t, m, err2),
+            dt_points: self.dt_points(t),
+        }
+    }
+
+    /// Partial sums of the pairs between two chunks of the same light curve, see [DmDtPartial]
+    ///
+    /// `a` and `b` are `(t, m, err2)` of the chunks, both `t` must be ascending slices. Chunks
+    /// could overlap in time, each pair gives `dm` of the later observation minus the earlier
+    /// one, an observation of `b` is considered as the later one if both have the same time.
+    pub fn cross_partial<Erf>(&self, a: (&[T], &[T], &[T]), b: (&[T], &[T], &[T])) -> DmDtPartial<T>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        let (t_a, m_a, err2_a) = a;
+        let (t_b, m_b, err2_b) = b;
+        let mut partial = DmDtPartial::zeros(self);
+        self.for_each_cross_pair(t_a, t_b, CrossPairs::Any, |idx_dt, i_a, i_b| {
+            let dm = if t_b[i_b] >= t_a[i_a] {
+                m_b[i_b] - m_a[i_a]
+            } else {
+                m_a[i_a] - m_b[i_b]
+            };
+            partial.dt_points[idx_dt] += 1;
+            if let CellIndex::Value(idx_dm) = self.dm_grid.idx(dm) {
+                partial.points[[idx_dt, idx_dm]] += 1;
+            }
+            self.add_weighted_gauss::<Erf>(
+                &mut partial.gausses,
+                idx_dt,
+                dm,
+                T::sqrt(err2_a[i_a] + err2_b[i_b]),
+                T::one(),
+            );
+        });
+        partial
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::ExactErf;
+
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn chunks_vs_whole() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 16, 1.25_f64, 16);
+        let t: Vec<_> = (0..101)
+            .map(|i| i as f64 + 0.1 * f64::sin(i as f64))
+            .collect();
+        let m: Vec<_> = t.iter().map(|&x| f64::sin(0.3 * x)).collect();
+        let err2 = vec![0.01; t.len()];
+        let whole = dmdt.partial::<ExactErf>(&t, &m, &err2);
+
+        let chunks = [0..30, 30..31, 31..70, 70..101];
+        let mut sum = DmDtPartial::zeros(&dmdt);
+        for (i, a) in chunks.iter().enumerate() {
+            let a = (&t[a.clone()], &m[a.clone()], &err2[a.clone()]);
+            sum += dmdt.partial::<ExactErf>(a.0, a.1, a.2);
+            for b in chunks[i + 1..].iter() {
+                let b = (&t[b.clone()], &m[b.clone()], &err2[b.clone()]);
+                // Order of chunks doesn't matter
+                sum += &dmdt.cross_partial::<ExactErf>(b, a);
+            }
+        }
+        let sum = DmDtPartial::zeros(&dmdt) + sum;
+
+        assert_eq!(sum.points, whole.points);
+        assert_eq!(sum.dt_points, whole.dt_points);
+        assert_abs_diff_eq!(
+            sum.gausses.as_slice().unwrap(),
+            whole.gausses.as_slice().unwrap(),
+            epsilon = 1e-10,
+        );
+        assert_abs_diff_eq!(
+            sum.cond_prob().as_slice().unwrap(),
+            dmdt.cond_prob::<ExactErf>(&t, &m, &err2)
+                .as_slice()
+                .unwrap(),
+            epsilon = 1e-12,
+        );
+    }
+
+    #[test]
+    fn merge_errors() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 16, 1.25_f64, 16);
+        let other = DmDt::from_lgdt_dm_limits(0.0_f64, 2.5_f64, 16, 1.25_f64, 16);
+        let mut partial = DmDtPartial::zeros(&dmdt);
+        assert_eq!(
+            partial.merge(&DmDtPartial::zeros(&other)),
+            Err(DmDtCompatibilityError::DtGridMismatch.into())
+        );
+
+        let mut truncated = DmDtPartial::zeros(&dmdt);
+        truncated.dt_points = Array1::zeros(15);
+        assert_eq!(
+            partial.merge(&truncated),
+            Err(DmDtShapeError::DtPointsShapeMismatch(15, 16).into())
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn add_incompatible() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 16, 1.25_f64, 16);
+        let other = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 16, 1.5_f64, 16);
+        let _ = DmDtPartial::zeros(&dmdt) + DmDtPartial::zeros(&other);
+    }
+}
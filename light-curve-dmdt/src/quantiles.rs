@@ -0,0 +1,276 @@
+use crate::util::{quantile_sorted, sort_floats};
+use crate::{DmDt, Float, GridTrait};
+
+use ndarray::{Array1, Array2};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Quantile computation method for [DmDt::dm_quantiles()]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum QuantileMethod {
+    /// Exact quantiles, linearly interpolated between the closest ranks
+    ///
+    /// It stores dm of all pairs in memory.
+    Exact,
+    /// Streaming approximation by P² algorithm
+    /// ([Jain & Chlamtac 1985](https://doi.org/10.1145/4372.4378))
+    ///
+    /// It uses constant memory for each dt cell and quantile. The result is exact for cells
+    /// having less than six pairs.
+    P2,
+}
+
+/// Quantiles of dm with pair counts for each dt grid cell
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DmDtQuantiles<T> {
+    /// Quantiles, the shape is (N dt, number of quantiles), NaN for cells having no pairs
+    pub values: Array2<T>,
+    /// Number of pairs in each dt cell, it is the same as [DmDt::dt_points()]
+    pub counts: Array1<u64>,
+}
+
+/// P² streaming quantile estimator
+#[derive(Clone, Debug)]
+struct P2Estimator<T> {
+    count: usize,
+    /// Marker heights, the first `count` values are observations for `count < 5`
+    heights: [T; 5],
+    /// Marker positions, one-based
+    positions: [T; 5],
+    desired_positions: [T; 5],
+    increments: [T; 5],
+}
+
+impl<T> P2Estimator<T>
+where
+    T: Float,
+{
+    fn new(p: T) -> Self {
+        let two = T::one() + T::one();
+        Self {
+            count: 0,
+            heights: [T::zero(); 5],
+            positions: [1, 2, 3, 4, 5].map(|x| T::value_from(x).unwrap()),
+            desired_positions: [
+                T::one(),
+                T::one() + two * p,
+                T::one() + two * two * p,
+                two + T::one() + two * p,
+                two * two + T::one(),
+            ],
+            increments: [
+                T::zero(),
+                T::half() * p,
+                p,
+                T::half() * (T::one() + p),
+                T::one(),
+            ],
+        }
+    }
+
+    fn push(&mut self, x: T) {
+        if self.count < 5 {
+            self.heights[self.count] = x;
+            self.count += 1;
+            if self.count == 5 {
+                sort_floats(&mut self.heights);
+            }
+            return;
+        }
+        self.count += 1;
+
+        let k = if x < self.heights[0] {
+            self.heights[0] = x;
+            0
+        } else if x >= self.heights[4] {
+            self.heights[4] = x;
+            3
+        } else {
+            // heights[k] <= x < heights[k + 1]
+            self.heights[1..4].partition_point(|&h| h <= x)
+        };
+        for position in self.positions[k + 1..].iter_mut() {
+            *position += T::one();
+        }
+        for (desired, &increment) in self.desired_positions.iter_mut().zip(&self.increments) {
+            *desired += increment;
+        }
+
+        for i in 1..4 {
+            let d = self.desired_positions[i] - self.positions[i];
+            if (d >= T::one() && self.positions[i + 1] - self.positions[i] > T::one())
+                || (d <= -T::one() && self.positions[i - 1] - self.positions[i] < -T::one())
+            {
+                let d = d.signum();
+                let parabolic = self.parabolic(i, d);
+                self.heights[i] =
+                    if self.heights[i - 1] < parabolic && parabolic < self.heights[i + 1] {
+                        parabolic
+                    } else {
+                        self.linear(i, d)
+                    };
+                self.positions[i] += d;
+            }
+        }
+    }
+
+    fn parabolic(&self, i: usize, d: T) -> T {
+        let (q, n) = (&self.heights, &self.positions);
+        q[i] + d / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]))
+    }
+
+    fn linear(&self, i: usize, d: T) -> T {
+        let j = if d > T::zero() { i + 1 } else { i - 1 };
+        self.heights[i]
+            + d * (self.heights[j] - self.heights[i]) / (self.positions[j] - self.positions[i])
+    }
+
+    fn quantile(&self, p: T) -> T {
+        match self.count {
+            0 => T::nan(),
+            1..=5 => {
+                let mut values = self.heights[..self.count].to_vec();
+                sort_floats(&mut values);
+                quantile_sorted(&values, p)
+            }
+            _ => self.heights[2],
+        }
+    }
+}
+
+impl<T> DmDt<T>
+where
+    T: Float,
+{
+    /// Quantiles of dm for each dt grid cell
+    ///
+    /// `t` must be an ascending slice, `q` is the quantile levels in `[0, 1]`. Values are computed
+    /// from the magnitude differences of the pairs directly, so dm grid doesn't affect them.
+    ///
+    /// # Panics
+    /// Panics if some of `q` is outside `[0, 1]`
+    pub fn dm_quantiles(
+        &self,
+        t: &[T],
+        m: &[T],
+        q: &[T],
+        method: QuantileMethod,
+    ) -> DmDtQuantiles<T> {
+        assert!(q.iter().all(|&q| q >= T::zero() && q <= T::one()));
+        let n_dt = self.dt_grid.cell_count();
+        let mut values = Array2::from_elem((n_dt, q.len()), T::nan());
+        let mut counts = Array1::zeros(n_dt);
+        match method {
+            QuantileMethod::Exact => {
+                let dm = self.pair_values_by_dt_cell(t, |i1, i2| m[i2] - m[i1]);
+                for ((mut row, count), mut dm) in
+                    values.rows_mut().into_iter().zip(&mut counts).zip(dm)
+                {
+                    *count = dm.len() as u64;
+                    if dm.is_empty() {
+                        continue;
+                    }
+                    sort_floats(&mut dm);
+                    for (x, &q) in row.iter_mut().zip(q) {
+                        *x = quantile_sorted(&dm, q);
+                    }
+                }
+            }
+            QuantileMethod::P2 => {
+                let mut estimators: Vec<Vec<_>> = (0..n_dt)
+                    .map(|_| q.iter().map(|&q| P2Estimator::new(q)).collect())
+                    .collect();
+                self.for_each_pair(t, |idx_dt, i1, i2| {
+                    counts[idx_dt] += 1;
+                    for estimator in estimators[idx_dt].iter_mut() {
+                        estimator.push(m[i2] - m[i1]);
+                    }
+                });
+                for (mut row, estimators) in values.rows_mut().into_iter().zip(estimators) {
+                    for ((x, &q), estimator) in row.iter_mut().zip(q).zip(estimators) {
+                        *x = estimator.quantile(q);
+                    }
+                }
+            }
+        }
+        DmDtQuantiles { values, counts }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use approx::assert_abs_diff_eq;
+    use ndarray::{Axis, s};
+
+    #[test]
+    fn exact_vs_p2() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 8, 1.25_f64, 16);
+        let t = Array1::linspace(0.0, 100.0, 1001);
+        let m = t.mapv(|x| f64::sin(x) + 0.1 * f64::cos(7.3 * x));
+        let (t, m) = (t.as_slice().unwrap(), m.as_slice().unwrap());
+        let q = [0.05, 0.16, 0.5, 0.84, 0.95];
+
+        let exact = dmdt.dm_quantiles(t, m, &q, QuantileMethod::Exact);
+        let p2 = dmdt.dm_quantiles(t, m, &q, QuantileMethod::P2);
+        assert_eq!(exact.counts, dmdt.dt_points(t));
+        assert_eq!(p2.counts, exact.counts);
+        // Quantiles are ordered
+        for row in exact.values.rows() {
+            assert!(row.iter().zip(row.iter().skip(1)).all(|(a, b)| a <= b));
+        }
+        assert_abs_diff_eq!(
+            p2.values.slice(s![1.., ..]).to_owned().as_slice().unwrap(),
+            exact
+                .values
+                .slice(s![1.., ..])
+                .to_owned()
+                .as_slice()
+                .unwrap(),
+            epsilon = 0.05,
+        );
+    }
+
+    #[test]
+    fn few_pairs() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 2, 1.25_f64, 16);
+        let t = [0.0, 2.0, 4.0, 1000.0];
+        let m = [0.0, 1.0, 3.0, 0.0];
+        let q = [0.0, 0.5, 1.0];
+
+        let exact = dmdt.dm_quantiles(&t, &m, &q, QuantileMethod::Exact);
+        let p2 = dmdt.dm_quantiles(&t, &m, &q, QuantileMethod::P2);
+        assert_eq!(exact.counts.to_vec(), [3, 0]);
+        assert_eq!(exact.values.row(0).to_vec(), [1.0, 2.0, 3.0]);
+        assert!(
+            exact
+                .values
+                .index_axis(Axis(0), 1)
+                .iter()
+                .all(|x| x.is_nan())
+        );
+        assert_eq!(p2.counts, exact.counts);
+        assert_eq!(p2.values.row(0), exact.values.row(0));
+    }
+
+    #[test]
+    fn five_pairs() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 2, 1.25_f64, 16);
+        // All pairs but the first and the last points are in the first dt cell
+        let t = [0.0, 1.0, 3.0, 10.5];
+        let m = [0.0, 1.0, 3.0, 5.0];
+        let q = [0.0, 0.25, 1.0];
+
+        let exact = dmdt.dm_quantiles(&t, &m, &q, QuantileMethod::Exact);
+        let p2 = dmdt.dm_quantiles(&t, &m, &q, QuantileMethod::P2);
+        assert_eq!(exact.counts.to_vec(), [5, 1]);
+        assert_eq!(exact.values.row(0).to_vec(), [1.0, 2.0, 4.0]);
+        assert_eq!(p2.counts, exact.counts);
+        assert_eq!(p2.values, exact.values);
+    }
+}
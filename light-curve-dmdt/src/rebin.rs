@@ -0,0 +1,157 @@
+use crate::{DmDt, DmDtShapeError, Float, Grid, GridTrait};
+
+use ndarray::{Array2, ArrayRef2};
+
+/// Coordinate in which the density is assumed to be uniform inside cells of the grid
+fn uniform_density_coordinate<T>(grid: &Grid<T>, x: T) -> T
+where
+    T: Float,
+{
+    match grid {
+        Grid::Lg(_) => {
+            if x.is_positive() {
+                x.log10()
+            } else {
+                T::neg_infinity()
+            }
+        }
+        Grid::SymLog(grid) => grid.transform(x),
+        Grid::Asinh(grid) => grid.transform(x),
+        Grid::Transform(grid) => grid.transform(x),
+        Grid::Array(_) | Grid::Linear(_) => x,
+    }
+}
+
+/// Fraction of each cell of `old` grid overlapping each cell of `new` grid
+///
+/// The shape is (old cell count, new cell count). Density is assumed to be uniform inside `old`
+/// cells in the coordinate given by [uniform_density_coordinate()].
+fn overlap_matrix<T>(old: &Grid<T>, new: &Grid<T>) -> Array2<T>
+where
+    T: Float,
+{
+    let old_borders = old
+        .get_borders()
+        .mapv(|x| uniform_density_coordinate(old, x));
+    let new_borders = new
+        .get_borders()
+        .mapv(|x| uniform_density_coordinate(old, x));
+    let mut overlap = Array2::zeros((old.cell_count(), new.cell_count()));
+    for (i, old_cell) in old_borders.windows(2).into_iter().enumerate() {
+        let (old_lo, old_hi) = (old_cell[0], old_cell[1]);
+        let old_size = old_hi - old_lo;
+        for (j, new_cell) in new_borders.windows(2).into_iter().enumerate() {
+            let (new_lo, new_hi) = (new_cell[0], new_cell[1]);
+            if new_hi <= old_lo {
+                continue;
+            }
+            if new_lo >= old_hi {
+                break;
+            }
+            let lo = T::max(old_lo, new_lo);
+            let hi = T::min(old_hi, new_hi);
+            // The ratio is exactly unity if new cell contains the old one
+            overlap[[i, j]] = (hi - lo) / old_size;
+        }
+    }
+    overlap
+}
+
+impl<T> DmDt<T>
+where
+    T: Float,
+{
+    /// Redistribute values of `map` defined by this object grids onto `new` grids
+    ///
+    /// `map` is considered as mass in the cells, uniformly distributed inside each cell along both
+    /// axes, in decimal logarithm space for [LgGrid](crate::LgGrid) and in the space of equal
+    /// cells for [SymLogGrid](crate::SymLogGrid), [AsinhGrid](crate::AsinhGrid) and
+    /// [TransformGrid](crate::TransformGrid). Each cell value is split
+    /// over the new cells proportionally to the overlap, and mass out of the new grids is
+    /// dropped. If new borders are a subset of the old ones, cells are aggregated exactly.
+    ///
+    /// The total mass is conserved, so it should be applied to unnormalised maps, like
+    /// [DmDt::points()] converted to float or [DmDt::gausses()], which could be normalised
+    /// afterwards, see [DmDt::normalise()].
+    pub fn rebin(&self, map: &ArrayRef2<T>, new: &Self) -> Result<Array2<T>, DmDtShapeError> {
+        self.check_map_shape(map)?;
+        let dt_overlap = overlap_matrix(&self.dt_grid, &new.dt_grid);
+        let dm_overlap = overlap_matrix(&self.dm_grid, &new.dm_grid);
+        Ok(dt_overlap.t().dot(map).dot(&dm_overlap))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::ExactErf;
+
+    use approx::assert_abs_diff_eq;
+    use ndarray::{Array1, array, s};
+
+    #[test]
+    fn subset_borders() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 16, 1.25_f64, 16);
+        let coarse = DmDt::from_grids(
+            Grid::array(dmdt.dt_grid.get_borders().slice(s![..;4]).to_owned()).unwrap(),
+            Grid::linear(-1.25, 1.25, 8),
+        );
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let m = t.mapv(f64::sin);
+        let err2 = Array1::from_elem(101, 0.01);
+        let (t, m, err2) = (
+            t.as_slice().unwrap(),
+            m.as_slice().unwrap(),
+            err2.as_slice().unwrap(),
+        );
+
+        let points = dmdt.points(t, m).mapv(|x| x as f64);
+        let rebinned = dmdt.rebin(&points, &coarse).unwrap();
+        assert_eq!(rebinned, coarse.points(t, m).mapv(|x| x as f64));
+
+        let gausses = dmdt.gausses::<ExactErf>(t, m, err2);
+        let rebinned = dmdt.rebin(&gausses, &coarse).unwrap();
+        assert_abs_diff_eq!(
+            rebinned.as_slice().unwrap(),
+            coarse.gausses::<ExactErf>(t, m, err2).as_slice().unwrap(),
+            epsilon = 1e-10,
+        );
+    }
+
+    #[test]
+    fn fractional_overlap() {
+        let dmdt = DmDt::from_grids(Grid::linear(0.0_f64, 2.0, 2), Grid::linear(0.0, 4.0, 4));
+        let new = DmDt::from_grids(Grid::linear(0.5_f64, 1.5, 1), Grid::linear(1.0, 5.0, 2));
+        let map = array![[1.0, 2.0, 3.0, 4.0], [5.0, 6.0, 7.0, 8.0]];
+        let rebinned = dmdt.rebin(&map, &new).unwrap();
+        // Half of each dt row, dm cells [1, 3) and [3, 4) with the rest out of the grid
+        assert_eq!(
+            rebinned,
+            array![[0.5 * (2.0 + 3.0 + 6.0 + 7.0), 0.5 * (4.0 + 8.0)]]
+        );
+
+        // Uniform density in lg space: half of [1, 100) lg-cell is [1, 10)
+        let lg = DmDt::from_grids(
+            Grid::log_from_start_end(1.0_f64, 100.0, 1),
+            Grid::linear(0.0, 1.0, 1),
+        );
+        let linear = DmDt::from_grids(
+            Grid::array(array![1.0, 10.0, 100.0]).unwrap(),
+            Grid::linear(0.0, 1.0, 1),
+        );
+        assert_abs_diff_eq!(
+            lg.rebin(&array![[1.0]], &linear)
+                .unwrap()
+                .as_slice()
+                .unwrap(),
+            &[0.5, 0.5][..],
+            epsilon = 1e-12,
+        );
+
+        assert_eq!(
+            dmdt.rebin(&Array2::zeros((4, 2)), &new),
+            Err(DmDtShapeError::MapShapeMismatch((4, 2), (2, 4)))
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use crate::util::{quantile_sorted, sort_floats};
+use crate::{DmDt, DmDtMapKind, ErfFloat, ErrorFunction, Float};
+
+use ndarray::{Array2, Array3, Axis, Zip};
+use rand::Rng;
+use rand_distr::{Distribution, StandardNormal};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Light curve resampling strategy for [DmDt::resampled_maps()]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Resampling {
+    /// Draw observations with replacement, keeping the number of observations
+    ///
+    /// Repeated observations give pairs with zero dt. They are out of grids starting from positive
+    /// dt, e.g. [LgGrid](crate::LgGrid), but they land in the first cell of grids starting from
+    /// zero, e.g. linear dt grids, and bias it.
+    Bootstrap,
+    /// Draw each magnitude from `N(m, err2)`
+    Noise,
+}
+
+/// dm–dt maps of the resampled light curves, see [DmDt::resampled_maps()]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ResampledMaps<T> {
+    /// Maps of the realisations, the shape is (number of realisations, N dt, N dm)
+    pub maps: Array3<T>,
+}
+
+impl<T> ResampledMaps<T>
+where
+    T: Float,
+{
+    /// Cell-wise mean over realisations
+    ///
+    /// # Panics
+    /// Panics if there are no realisations
+    pub fn mean(&self) -> Array2<T> {
+        let n = self.maps.len_of(Axis(0));
+        assert!(n > 0);
+        self.maps.sum_axis(Axis(0)) / T::value_from(n).unwrap()
+    }
+
+    /// Cell-wise sample standard deviation over realisations
+    ///
+    /// # Panics
+    /// Panics if there are less than two realisations
+    pub fn std(&self) -> Array2<T> {
+        let n = self.maps.len_of(Axis(0));
+        assert!(n > 1);
+        let mean = self.mean();
+        let mut sum2 = Array2::zeros(mean.dim());
+        for map in self.maps.outer_iter() {
+            Zip::from(&mut sum2)
+                .and(&map)
+                .and(&mean)
+                .for_each(|s, &x, &mu| *s += (x - mu).powi(2));
+        }
+        sum2.mapv_into(|s| T::sqrt(s / T::value_from(n - 1).unwrap()))
+    }
+
+    /// Cell-wise quantiles over realisations
+    ///
+    /// The shape of the output is (number of quantiles, N dt, N dm), quantiles are linearly
+    /// interpolated between the closest ranks.
+    ///
+    /// # Panics
+    /// Panics if some of `q` is outside `[0, 1]` or there are no realisations
+    pub fn quantiles(&self, q: &[T]) -> Array3<T> {
+        assert!(q.iter().all(|&q| q >= T::zero() && q <= T::one()));
+        let (_, n_dt, n_dm) = self.maps.dim();
+        let mut output = Array3::zeros((q.len(), n_dt, n_dm));
+        Zip::from(output.lanes_mut(Axis(0)))
+            .and(self.maps.lanes(Axis(0)))
+            .for_each(|mut output, values| {
+                let mut values = values.to_vec();
+                sort_floats(&mut values);
+                for (x, &q) in output.iter_mut().zip(q) {
+                    *x = quantile_sorted(&values, q);
+                }
+            });
+        output
+    }
+}
+
+impl<T> DmDt<T>
+where
+    T: ErfFloat,
+{
+    /// dm–dt maps of `n_realisations` resampled light curves
+    ///
+    /// `lc` is `(t, m, err2)` triple, `t` must be an ascending slice. `kind` is the map to compute
+    /// for each realisation, counts
+    /// of [DmDtMapKind::Points] are converted to float. `resampling` specifies how to resample
+    /// the light curve, see [Resampling]. `rng` is a random number generator, use a seeded one
+    /// for reproducible results. Use [ResampledMaps] methods to get mean, standard deviation or
+    /// quantile maps.
+    pub fn resampled_maps<Erf, R>(
+        &self,
+        lc: (&[T], &[T], &[T]),
+        kind: DmDtMapKind,
+        resampling: Resampling,
+        n_realisations: usize,
+        rng: &mut R,
+    ) -> ResampledMaps<T>
+    where
+        Erf: ErrorFunction<T>,
+        R: Rng + ?Sized,
+        StandardNormal: Distribution<T>,
+    {
+        let (t, m, err2) = lc;
+        let (n_dt, n_dm) = self.shape();
+        let mut maps = Array3::zeros((n_realisations, n_dt, n_dm));
+        let mut t_resampled = t.to_vec();
+        let mut m_resampled = m.to_vec();
+        let mut err2_resampled = err2.to_vec();
+        for mut map in maps.outer_iter_mut() {
+            match resampling {
+                Resampling::Bootstrap => {
+                    // Sorted indices keep t ascending
+                    let mut indices: Vec<_> =
+                        (0..t.len()).map(|_| rng.random_range(0..t.len())).collect();
+                    indices.sort_unstable();
+                    for (j, &i) in indices.iter().enumerate() {
+                        t_resampled[j] = t[i];
+                        m_resampled[j] = m[i];
+                        err2_resampled[j] = err2[i];
+                    }
+                }
+                Resampling::Noise => {
+                    for ((m_resampled, &m), &err2) in m_resampled.iter_mut().zip(m).zip(err2) {
+                        let z: T = StandardNormal.sample(rng);
+                        *m_resampled = m + err2.sqrt() * z;
+                    }
+                }
+            }
+            match kind {
+                DmDtMapKind::Points => {
+                    let mut points = Array2::zeros(self.shape());
+                    self.add_points(&mut points, &t_resampled, &m_resampled);
+                    map.zip_mut_with(&points, |x, &count| *x = T::approx_from(count).unwrap());
+                }
+                DmDtMapKind::Gausses => {
+                    self.add_gausses::<Erf>(&mut map, &t_resampled, &m_resampled, &err2_resampled)
+                }
+                DmDtMapKind::CondProb => self.fill_cond_prob::<Erf>(
+                    &mut map,
+                    &t_resampled,
+                    &m_resampled,
+                    &err2_resampled,
+                ),
+            }
+        }
+        ResampledMaps { maps }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::ExactErf;
+
+    use approx::assert_abs_diff_eq;
+    use ndarray::{Array1, s};
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    #[test]
+    fn zero_noise() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 16, 1.25_f64, 16);
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let m = t.mapv(f64::sin);
+        let err2 = Array1::zeros(101);
+        let (t, m, err2) = (
+            t.as_slice().unwrap(),
+            m.as_slice().unwrap(),
+            err2.as_slice().unwrap(),
+        );
+
+        let resampled = dmdt.resampled_maps::<ExactErf, _>(
+            (t, m, err2),
+            DmDtMapKind::Points,
+            Resampling::Noise,
+            3,
+            &mut Xoshiro256PlusPlus::seed_from_u64(0),
+        );
+        let points = dmdt.points(t, m).mapv(|x| x as f64);
+        assert_eq!(resampled.mean(), points);
+        assert!(resampled.std().iter().all(|&x| x == 0.0));
+        let quantiles = resampled.quantiles(&[0.0, 0.5, 1.0]);
+        for q in quantiles.outer_iter() {
+            assert_eq!(q, points);
+        }
+    }
+
+    #[test]
+    fn bootstrap_and_noise() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 16, 1.25_f64, 16);
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let m = t.mapv(f64::sin);
+        let err2 = Array1::from_elem(101, 0.01);
+        let (t, m, err2) = (
+            t.as_slice().unwrap(),
+            m.as_slice().unwrap(),
+            err2.as_slice().unwrap(),
+        );
+        let cond_prob = dmdt.cond_prob::<ExactErf>(t, m, err2);
+
+        for resampling in [Resampling::Bootstrap, Resampling::Noise] {
+            let resampled = dmdt.resampled_maps::<ExactErf, _>(
+                (t, m, err2),
+                DmDtMapKind::CondProb,
+                resampling,
+                16,
+                &mut Xoshiro256PlusPlus::seed_from_u64(0),
+            );
+            assert_eq!(
+                resampled,
+                dmdt.resampled_maps::<ExactErf, _>(
+                    (t, m, err2),
+                    DmDtMapKind::CondProb,
+                    resampling,
+                    16,
+                    &mut Xoshiro256PlusPlus::seed_from_u64(0),
+                )
+            );
+
+            let mean = resampled.mean();
+            let std = resampled.std();
+            assert!(std.iter().any(|&x| x > 0.0));
+            // Long dt rows have many pairs, so they are stable
+            assert_abs_diff_eq!(
+                mean.slice(s![8.., ..]).as_slice().unwrap(),
+                cond_prob.slice(s![8.., ..]).to_owned().as_slice().unwrap(),
+                epsilon = 0.02,
+            );
+
+            let quantiles = resampled.quantiles(&[0.1, 0.5, 0.9]);
+            assert!(
+                Zip::from(quantiles.index_axis(Axis(0), 0))
+                    .and(quantiles.index_axis(Axis(0), 1))
+                    .and(quantiles.index_axis(Axis(0), 2))
+                    .all(|&a, &b, &c| a <= b && b <= c)
+            );
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use crate::{CellIndex, DmDt, ErfFloat, ErrorFunction, Float, GridTrait};
+
+use ndarray::{Array1, Array2};
+use rand::Rng;
+use rand::seq::index;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Pair sampling strategy for [DmDt::sampled_points()] and [DmDt::sampled_gausses()]
+///
+/// Each strategy selects a subset of pairs of observations and weights them to make the map
+/// an estimate of the map of all pairs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PairSampling {
+    /// `n_pairs` pairs drawn uniformly with replacement from all pairs
+    ///
+    /// Each pair is weighted by the ratio of the total number of pairs to `n_pairs`, so the
+    /// estimate is unbiased.
+    Uniform { n_pairs: usize },
+    /// Up to `pairs_per_cell` pairs drawn uniformly without replacement from the pairs of each dt
+    /// cell
+    ///
+    /// Each pair is weighted by the ratio of the number of pairs in its dt cell to the number of
+    /// drawn pairs in the cell, so the estimate is unbiased and dt rows are exact in total.
+    /// Cells having not more than `pairs_per_cell` pairs are used as is.
+    Stratified { pairs_per_cell: usize },
+    /// Each observation is paired with its `k` nearest neighbours in time
+    ///
+    /// Neighbours are looked for both before and after the observation, the earlier one wins a
+    /// tie, and a pair is selected once even if both observations are neighbours of each other.
+    /// The selection is deterministic, it favours short dt and gives no pairs for long dt if
+    /// `k` is small. Each pair is weighted by the ratio of the number of pairs in its dt cell to
+    /// the number of selected pairs in the cell, so it is the estimate of the full map for the
+    /// dt cells having at least one selected pair only.
+    KNearest { k: usize },
+}
+
+/// Sampled pair of observations with its dt cell index and weight
+struct SampledPair<T> {
+    idx_dt: usize,
+    i1: usize,
+    i2: usize,
+    weight: T,
+}
+
+impl<T> DmDt<T>
+where
+    T: Float,
+{
+    /// Selects pairs of observations according to `sampling`
+    ///
+    /// `t` must be an ascending slice. Only pairs having dt inside the dt grid are returned.
+    fn sample_pairs<R>(&self, t: &[T], sampling: PairSampling, rng: &mut R) -> Vec<SampledPair<T>>
+    where
+        R: Rng + ?Sized,
+    {
+        match sampling {
+            PairSampling::Uniform { n_pairs } => self.sample_uniform_pairs(t, n_pairs, rng),
+            PairSampling::Stratified { pairs_per_cell } => {
+                self.sample_stratified_pairs(t, pairs_per_cell, rng)
+            }
+            PairSampling::KNearest { k } => self.sample_k_nearest_pairs(t, k),
+        }
+    }
+
+    fn sample_uniform_pairs<R>(&self, t: &[T], n_pairs: usize, rng: &mut R) -> Vec<SampledPair<T>>
+    where
+        R: Rng + ?Sized,
+    {
+        let n = t.len();
+        if n < 2 || n_pairs == 0 {
+            return vec![];
+        }
+        let total_pairs = n as u64 * (n as u64 - 1) / 2;
+        let weight = T::approx_from(total_pairs).unwrap() / T::value_from(n_pairs).unwrap();
+        (0..n_pairs)
+            .filter_map(|_| {
+                let i = rng.random_range(0..n);
+                let mut j = rng.random_range(0..n - 1);
+                if j >= i {
+                    j += 1;
+                }
+                let (i1, i2) = (usize::min(i, j), usize::max(i, j));
+                match self.dt_grid.idx(t[i2] - t[i1]) {
+                    CellIndex::Value(idx_dt) => Some(SampledPair {
+                        idx_dt,
+                        i1,
+                        i2,
+                        weight,
+                    }),
+                    CellIndex::LowerMin | CellIndex::GreaterMax => None,
+                }
+            })
+            .collect()
+    }
+
+    fn sample_stratified_pairs<R>(
+        &self,
+        t: &[T],
+        pairs_per_cell: usize,
+        rng: &mut R,
+    ) -> Vec<SampledPair<T>>
+    where
+        R: Rng + ?Sized,
+    {
+        let dt_points = self.dt_points(t);
+        // Sorted ranks of the selected pairs among the pairs of each dt cell, the pairs are
+        // ordered by the first and then by the second observation
+        let selected: Vec<Vec<u64>> = dt_points
+            .iter()
+            .map(|&count| {
+                if count <= pairs_per_cell as u64 {
+                    return (0..count).collect();
+                }
+                let mut ranks: Vec<_> = index::sample(rng, count as usize, pairs_per_cell)
+                    .into_iter()
+                    .map(|rank| rank as u64)
+                    .collect();
+                ranks.sort_unstable();
+                ranks
+            })
+            .collect();
+        let weights: Vec<T> = dt_points
+            .iter()
+            .zip(selected.iter())
+            .map(|(&count, ranks)| {
+                if ranks.is_empty() {
+                    T::zero()
+                } else {
+                    T::approx_from(count).unwrap() / T::value_from(ranks.len()).unwrap()
+                }
+            })
+            .collect();
+
+        let mut pairs = Vec::with_capacity(selected.iter().map(Vec::len).sum());
+        // Number of pairs of each cell seen so far and the position in the selected ranks
+        let mut offsets = vec![0_u64; dt_points.len()];
+        let mut positions = vec![0_usize; dt_points.len()];
+        self.for_each_dt_cell_range(t, |i1, idx_dt, range| {
+            let end = offsets[idx_dt] + range.len() as u64;
+            let ranks = &selected[idx_dt];
+            while positions[idx_dt] < ranks.len() && ranks[positions[idx_dt]] < end {
+                let i2 = range.start + (ranks[positions[idx_dt]] - offsets[idx_dt]) as usize;
+                pairs.push(SampledPair {
+                    idx_dt,
+                    i1,
+                    i2,
+                    weight: weights[idx_dt],
+                });
+                positions[idx_dt] += 1;
+            }
+            offsets[idx_dt] = end;
+        });
+        pairs
+    }
+
+    fn sample_k_nearest_pairs(&self, t: &[T], k: usize) -> Vec<SampledPair<T>> {
+        // Merge the earlier and the later observations by dt with two pointers
+        let mut neighbours = vec![];
+        for i in 0..t.len() {
+            let (mut left, mut right) = (i, i + 1);
+            for _ in 0..k {
+                let take_left = match (left > 0, right < t.len()) {
+                    (false, false) => break,
+                    (true, false) => true,
+                    (false, true) => false,
+                    (true, true) => t[i] - t[left - 1] <= t[right] - t[i],
+                };
+                if take_left {
+                    left -= 1;
+                    neighbours.push((left, i));
+                } else {
+                    neighbours.push((i, right));
+                    right += 1;
+                }
+            }
+        }
+        neighbours.sort_unstable();
+        neighbours.dedup();
+
+        let mut selected_points: Array1<u64> = Array1::zeros(self.dt_grid.cell_count());
+        let mut pairs: Vec<_> = neighbours
+            .into_iter()
+            .filter_map(|(i1, i2)| match self.dt_grid.idx(t[i2] - t[i1]) {
+                CellIndex::Value(idx_dt) => {
+                    selected_points[idx_dt] += 1;
+                    Some(SampledPair {
+                        idx_dt,
+                        i1,
+                        i2,
+                        weight: T::zero(),
+                    })
+                }
+                CellIndex::LowerMin | CellIndex::GreaterMax => None,
+            })
+            .collect();
+        let weights = self
+            .dt_points(t)
+            .iter()
+            .zip(selected_points.iter())
+            .map(|(&count, &selected)| {
+                if selected == 0 {
+                    T::zero()
+                } else {
+                    T::approx_from(count).unwrap() / T::approx_from(selected).unwrap()
+                }
+            })
+            .collect::<Vec<_>>();
+        for pair in pairs.iter_mut() {
+            pair.weight = weights[pair.idx_dt];
+        }
+        pairs
+    }
+
+    /// Estimate of [DmDt::points()] from a sample of pairs
+    ///
+    /// `t` must be an ascending slice. `sampling` specifies the pair selection, see
+    /// [PairSampling], `rng` is a random number generator, use a seeded one for reproducible
+    /// results. Each selected pair brings its weight instead of unity value.
+    pub fn sampled_points<R>(
+        &self,
+        t: &[T],
+        m: &[T],
+        sampling: PairSampling,
+        rng: &mut R,
+    ) -> Array2<T>
+    where
+        R: Rng + ?Sized,
+    {
+        let mut a = Array2::zeros(self.shape());
+        for SampledPair {
+            idx_dt,
+            i1,
+            i2,
+            weight,
+        } in self.sample_pairs(t, sampling, rng)
+        {
+            if let CellIndex::Value(idx_dm) = self.dm_grid.idx(m[i2] - m[i1]) {
+                a[(idx_dt, idx_dm)] += weight;
+            }
+        }
+        a
+    }
+
+    /// Estimate of [DmDt::gausses()] from a sample of pairs
+    ///
+    /// `t` must be an ascending slice. `sampling` specifies the pair selection, see
+    /// [PairSampling], `rng` is a random number generator, use a seeded one for reproducible
+    /// results. Each selected pair brings Gaussian distribution multiplied by its weight.
+    pub fn sampled_gausses<Erf, R>(
+        &self,
+        t: &[T],
+        m: &[T],
+        err2: &[T],
+        sampling: PairSampling,
+        rng: &mut R,
+    ) -> Array2<T>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+        R: Rng + ?Sized,
+    {
+        let mut a = Array2::zeros(self.shape());
+        for SampledPair {
+            idx_dt,
+            i1,
+            i2,
+            weight,
+        } in self.sample_pairs(t, sampling, rng)
+        {
+            self.add_weighted_gauss::<Erf>(
+                &mut a,
+                idx_dt,
+                m[i2] - m[i1],
+                T::sqrt(err2[i1] + err2[i2]),
+                weight,
+            );
+        }
+        a
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::ExactErf;
+
+    use crate::LinearGrid;
+
+    use approx::assert_abs_diff_eq;
+    use ndarray::Axis;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
+
+    fn light_curve() -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        let t: Vec<_> = (0..200)
+            .map(|i| i as f64 * 0.5 + 0.1 * f64::sin(i as f64).abs())
+            .collect();
+        let m = t.iter().map(|&x| f64::sin(x)).collect();
+        let err2 = vec![0.001; t.len()];
+        (t, m, err2)
+    }
+
+    #[test]
+    fn exhaustive_sampling() {
+        let dmdt = DmDt::from_lgdt_dm_limits(-0.5_f64, 2.0_f64, 32, 1.25_f64, 32);
+        let (t, m, err2) = light_curve();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+
+        let points = dmdt.points(&t, &m).mapv(|x| x as f64);
+        let gausses = dmdt.gausses::<ExactErf>(&t, &m, &err2);
+        for sampling in [
+            PairSampling::Stratified {
+                pairs_per_cell: usize::MAX,
+            },
+            PairSampling::KNearest { k: t.len() },
+        ] {
+            assert_eq!(dmdt.sampled_points(&t, &m, sampling, &mut rng), points);
+            assert_abs_diff_eq!(
+                dmdt.sampled_gausses::<ExactErf, _>(&t, &m, &err2, sampling, &mut rng)
+                    .as_slice()
+                    .unwrap(),
+                gausses.as_slice().unwrap(),
+                epsilon = 1e-9,
+            );
+        }
+    }
+
+    #[test]
+    fn estimates() {
+        let dmdt = DmDt::from_lgdt_dm_limits(-0.5_f64, 2.0_f64, 16, 1.25_f64, 16);
+        let (t, m, _err2) = light_curve();
+        let points = dmdt.points(&t, &m).mapv(|x| x as f64);
+        let dt_points = dmdt.dt_points(&t).mapv(|x| x as f64);
+
+        let sampling = PairSampling::Stratified {
+            pairs_per_cell: 500,
+        };
+        let stratified =
+            dmdt.sampled_points(&t, &m, sampling, &mut Xoshiro256PlusPlus::seed_from_u64(0));
+        // Each cell is sampled, so dt rows are close to the full ones. They are not exact,
+        // because the weights make dt_points exact, while pairs out of the dm grid vary
+        assert_abs_diff_eq!(
+            (stratified.sum_axis(Axis(1)) / &dt_points)
+                .as_slice()
+                .unwrap(),
+            (points.sum_axis(Axis(1)) / &dt_points).as_slice().unwrap(),
+            epsilon = 0.05,
+        );
+        assert_eq!(
+            stratified,
+            dmdt.sampled_points(&t, &m, sampling, &mut Xoshiro256PlusPlus::seed_from_u64(0))
+        );
+
+        let sampling = PairSampling::Uniform { n_pairs: 5000 };
+        let uniform =
+            dmdt.sampled_points(&t, &m, sampling, &mut Xoshiro256PlusPlus::seed_from_u64(0));
+        assert_abs_diff_eq!(uniform.sum() / points.sum(), 1.0, epsilon = 0.05);
+
+        let sampling = PairSampling::KNearest { k: 5 };
+        let k_nearest =
+            dmdt.sampled_points(&t, &m, sampling, &mut Xoshiro256PlusPlus::seed_from_u64(0));
+        // Short dt rows are sampled and scaled to the full number of pairs
+        assert!(points.row(0).sum() > 0.0);
+        assert_abs_diff_eq!(k_nearest.row(0).sum(), points.row(0).sum(), epsilon = 1e-9);
+        assert!(k_nearest.row(15).iter().all(|&x| x == 0.0));
+    }
+
+    #[test]
+    fn k_nearest_in_time() {
+        // The nearest neighbour of the third observation is the second one, the fourth one is
+        // far away
+        let dmdt = DmDt::from_grids(
+            LinearGrid::new(0.0, 20.0, 20),
+            LinearGrid::new(-1.0, 1.0, 1),
+        );
+        let t = [0.0, 5.0, 5.5, 15.0];
+        let m = [0.0; 4];
+        let sampling = PairSampling::KNearest { k: 1 };
+        let k_nearest =
+            dmdt.sampled_points(&t, &m, sampling, &mut Xoshiro256PlusPlus::seed_from_u64(0));
+        // Selected dt are 5.0, 0.5 and 9.5, the first one shares its cell with unselected 5.5,
+        // so it has weight of two
+        let mut selected = Array1::zeros(20);
+        selected[5] = 2.0;
+        selected[0] = 1.0;
+        selected[9] = 1.0;
+        assert_eq!(k_nearest.column(0), selected);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::util::{quantile_sorted, sort_floats};
+use crate::{DmDt, Float, GridTrait};
+
+use ndarray::{Array1, Zip};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Structure function estimator, see [DmDt::structure_function()]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum StructureFunction {
+    /// Root mean square of dm: `sqrt(<dm^2>)`
+    Rms,
+    /// Robust estimate of dm standard deviation from its interquartile range: `0.741 IQR(dm)`
+    Iqr,
+    /// Robust estimate of dm standard deviation from its median absolute deviation:
+    /// `1.4826 median(|dm - median(dm)|)`
+    Mad,
+    /// Root mean square of dm corrected for the observation errors:
+    /// `sqrt(<dm^2 - err2_1 - err2_2>)`, it is zero if the correction exceeds `<dm^2>`
+    ErrorCorrected,
+}
+
+/// Structure function values with pair counts for each dt grid cell
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DmDtStructureFunction<T> {
+    /// Structure function value of each dt cell, NaN for cells having no pairs
+    pub values: Array1<T>,
+    /// Number of pairs in each dt cell, it is the same as [DmDt::dt_points()]
+    pub counts: Array1<u64>,
+}
+
+impl<T> DmDt<T>
+where
+    T: Float,
+{
+    /// Collects `f(i1, i2)` of each pair of points for each dt grid cell
+    ///
+    /// `t` must be an ascending slice
+    pub(crate) fn pair_values_by_dt_cell<F>(&self, t: &[T], mut f: F) -> Vec<Vec<T>>
+    where
+        F: FnMut(usize, usize) -> T,
+    {
+        let mut values = vec![vec![]; self.dt_grid.cell_count()];
+        self.for_each_pair(t, |idx_dt, i1, i2| values[idx_dt].push(f(i1, i2)));
+        values
+    }
+
+    /// Structure function for each dt grid cell
+    ///
+    /// `t` must be an ascending slice, `err2` is used by [StructureFunction::ErrorCorrected]
+    /// only. Values are computed from the magnitude differences of the pairs directly, so dm
+    /// grid doesn't affect them. [StructureFunction::Rms] and [StructureFunction::ErrorCorrected]
+    /// use running sums, while [StructureFunction::Iqr] and [StructureFunction::Mad] store dm of
+    /// all pairs in memory.
+    pub fn structure_function(
+        &self,
+        t: &[T],
+        m: &[T],
+        err2: &[T],
+        sf: StructureFunction,
+    ) -> DmDtStructureFunction<T> {
+        match sf {
+            StructureFunction::Rms | StructureFunction::ErrorCorrected => {
+                // Running sums of dm^2, corrected for errors if needed, and pair counts
+                let n_dt = self.dt_grid.cell_count();
+                let mut sums: Array1<T> = Array1::zeros(n_dt);
+                let mut counts: Array1<u64> = Array1::zeros(n_dt);
+                self.for_each_pair(t, |idx_dt, i1, i2| {
+                    let mut dm2 = (m[i2] - m[i1]).powi(2);
+                    if sf == StructureFunction::ErrorCorrected {
+                        dm2 -= err2[i1] + err2[i2];
+                    }
+                    sums[idx_dt] += dm2;
+                    counts[idx_dt] += 1;
+                });
+                let values = Zip::from(&sums).and(&counts).map_collect(|&sum, &count| {
+                    if count == 0 {
+                        return T::nan();
+                    }
+                    T::max(sum / T::approx_from(count).unwrap(), T::zero()).sqrt()
+                });
+                DmDtStructureFunction { values, counts }
+            }
+            StructureFunction::Iqr | StructureFunction::Mad => {
+                let dm = self.pair_values_by_dt_cell(t, |i1, i2| m[i2] - m[i1]);
+                let counts = dm.iter().map(|v| v.len() as u64).collect();
+                let values = dm
+                    .into_iter()
+                    .map(|mut v| {
+                        if v.is_empty() {
+                            return T::nan();
+                        }
+                        sort_floats(&mut v);
+                        if sf == StructureFunction::Iqr {
+                            return T::from(0.741).unwrap()
+                                * (quantile_sorted(&v, T::from(0.75).unwrap())
+                                    - quantile_sorted(&v, T::from(0.25).unwrap()));
+                        }
+                        let median = quantile_sorted(&v, T::half());
+                        let mut deviations: Vec<_> =
+                            v.into_iter().map(|dm| (dm - median).abs()).collect();
+                        sort_floats(&mut deviations);
+                        T::from(1.4826).unwrap() * quantile_sorted(&deviations, T::half())
+                    })
+                    .collect();
+                DmDtStructureFunction { values, counts }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn sine() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 16, 1.25_f64, 16);
+        let t = Array1::linspace(0.0, 100.0, 1001);
+        let m = t.mapv(f64::sin);
+        let err2 = Array1::from_elem(1001, 0.01);
+        let (t, m, err2) = (
+            t.as_slice().unwrap(),
+            m.as_slice().unwrap(),
+            err2.as_slice().unwrap(),
+        );
+
+        let rms = dmdt.structure_function(t, m, err2, StructureFunction::Rms);
+        assert_eq!(rms.counts, dmdt.dt_points(t));
+        // Sine of uniformly distributed phase has variance 1/2, so for dt much longer than the
+        // period dm has variance 1
+        assert_abs_diff_eq!(rms.values[15], 1.0, epsilon = 0.05);
+
+        let corrected = dmdt.structure_function(t, m, err2, StructureFunction::ErrorCorrected);
+        assert_eq!(corrected.counts, rms.counts);
+        for (&x, &y) in corrected.values.iter().zip(rms.values.iter()) {
+            assert_abs_diff_eq!(
+                x,
+                f64::sqrt(f64::max(y.powi(2) - 0.02, 0.0)),
+                epsilon = 1e-12
+            );
+        }
+
+        let iqr = dmdt.structure_function(t, m, err2, StructureFunction::Iqr);
+        let mad = dmdt.structure_function(t, m, err2, StructureFunction::Mad);
+        assert!(iqr.values.iter().all(|x| x.is_finite() && *x >= 0.0));
+        assert!(mad.values.iter().all(|x| x.is_finite() && *x >= 0.0));
+    }
+
+    #[test]
+    fn gaussian_quantiles() {
+        // dm values are deciles of the standard normal distribution, each pair is far from others
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 1, 1.25_f64, 16);
+        let dm = [
+            -1.2815516, -0.8416212, -0.5244005, -0.2533471, 0.0, 0.2533471, 0.5244005, 0.8416212,
+            1.2815516,
+        ];
+        let mut t = vec![];
+        let mut m = vec![];
+        for (i, &dm) in dm.iter().enumerate() {
+            t.extend([1e3 * i as f64, 1e3 * i as f64 + 2.0]);
+            m.extend([0.0, dm]);
+        }
+        let err2 = vec![0.0; t.len()];
+
+        let iqr = dmdt.structure_function(&t, &m, &err2, StructureFunction::Iqr);
+        assert_eq!(iqr.counts[0], 9);
+        assert_abs_diff_eq!(iqr.values[0], 0.741 * 2.0 * 0.5244005, epsilon = 1e-12);
+        let mad = dmdt.structure_function(&t, &m, &err2, StructureFunction::Mad);
+        assert_abs_diff_eq!(mad.values[0], 1.4826 * 0.5244005, epsilon = 1e-12);
+
+        let empty = dmdt.structure_function(&[0.0], &[0.0], &[0.0], StructureFunction::Rms);
+        assert_eq!(empty.counts[0], 0);
+        assert!(empty.values[0].is_nan());
+    }
+}
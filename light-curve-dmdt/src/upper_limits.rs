@@ -0,0 +1,234 @@
+use crate::{CrossPairs, DmDt, ErfFloat, ErrorFunction, Float, GridTrait};
+
+use ndarray::{Array3, ArrayRef2, Axis, s};
+
+/// Output channels of [DmDt::gausses_with_upper_limits()]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpperLimitChannel {
+    /// Single channel with both detection–detection and detection–upper limit pairs
+    Merged,
+    /// Two channels: detection–detection pairs first, detection–upper limit pairs second
+    Separate,
+}
+
+impl<T> DmDt<T>
+where
+    T: Float,
+{
+    /// Spreads unit mass over the cells of the `idx_dt` row allowed by the bound on dm
+    ///
+    /// If `lower` is true then the dm is known to be larger than `bound`, otherwise it is known to
+    /// be smaller. The bound has Gaussian uncertainty `bound_err`, so the mass density is
+    /// proportional to the probability of dm to satisfy the bound. The density is integrated over
+    /// the cells and normalised to unity over the dm grid, nothing is added if the allowed range
+    /// doesn't overlap the grid.
+    fn add_censored_helper<Erf>(
+        &self,
+        a: &mut ArrayRef2<T>,
+        idx_dt: usize,
+        bound: T,
+        bound_err: T,
+        lower: bool,
+    ) where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        let min_dx = Erf::min_dx_nonzero_normal_cdf(bound_err);
+        let max_dx = Erf::max_dx_nonunity_normal_cdf(bound_err);
+        let (start, end) = (self.dm_grid.get_start(), self.dm_grid.get_end());
+        let (sign, min_x, max_x) = if lower {
+            if bound >= end {
+                return;
+            }
+            (T::one(), bound + min_dx, end)
+        } else {
+            if bound <= start {
+                return;
+            }
+            (-T::one(), start, bound - min_dx)
+        };
+        // Integral of the probability to satisfy the bound from -inf to x, along the allowed
+        // direction: dx Phi(dx / sigma) + sigma phi(dx / sigma)
+        let integral = move |x: T| {
+            let dx = sign * (x - bound);
+            if dx <= min_dx {
+                T::zero()
+            } else if dx >= max_dx {
+                dx
+            } else {
+                let z = dx / bound_err;
+                dx * Erf::normal_cdf(dx, T::zero(), bound_err)
+                    + bound_err
+                        * T::FRAC_2_SQRT_PI()
+                        * T::FRAC_1_SQRT_2()
+                        * T::half()
+                        * T::exp(-T::half() * z * z)
+            }
+        };
+        // Antiderivative of the density along the grid, it is increasing for both signs
+        let integral_start = sign * integral(start);
+        let total = sign * integral(end) - integral_start;
+        let Some((min_idx_dm, values)) =
+            Self::cdf_cell_integrals(&self.dm_grid, min_x, max_x, move |x| {
+                (sign * integral(x) - integral_start) / total
+            })
+        else {
+            return;
+        };
+        a.slice_mut(s![idx_dt, min_idx_dm..])
+            .iter_mut()
+            .zip(values)
+            .for_each(|(cell, value)| *cell += value);
+    }
+
+    /// dm–dt map of detections and upper limits
+    ///
+    /// `detections` is `(t, m, err2)` triple and `upper_limits` is `(t, m_lim)` pair, both `t`
+    /// must be ascending slices. Detection–detection pairs are represented as in
+    /// [DmDt::gausses()]. Each detection–upper limit pair is a censored observation: if the upper
+    /// limit `m_lim` is later than the detection `m`, then `dm > m_lim - m`, otherwise
+    /// `dm < m - m_lim`. Such a pair spreads unit mass uniformly over the allowed one-sided dm
+    /// range inside the dm grid of its dt row, the bound is smeared with the detection error, so
+    /// the cells near the bound get the mass weighted by the probability to be allowed. Pairs which
+    /// allowed range doesn't overlap the dm grid are ignored, as well as upper limit–upper limit
+    /// pairs which bring no information.
+    ///
+    /// Output shape is (number of channels, N dt, N dm), see [UpperLimitChannel] for the
+    /// channels.
+    pub fn gausses_with_upper_limits<Erf>(
+        &self,
+        detections: (&[T], &[T], &[T]),
+        upper_limits: (&[T], &[T]),
+        channel: UpperLimitChannel,
+    ) -> Array3<T>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        let (t, m, err2) = detections;
+        let (t_lim, m_lim) = upper_limits;
+        let (n_dt, n_dm) = self.shape();
+        let n_channels = match channel {
+            UpperLimitChannel::Merged => 1,
+            UpperLimitChannel::Separate => 2,
+        };
+        let mut a = Array3::zeros((n_channels, n_dt, n_dm));
+
+        self.add_gausses::<Erf>(&mut a.index_axis_mut(Axis(0), 0), t, m, err2);
+
+        let mut censored = a.index_axis_mut(Axis(0), n_channels - 1);
+        self.for_each_cross_pair(t, t_lim, CrossPairs::Any, |idx_dt, i, i_lim| {
+            let err = T::sqrt(err2[i]);
+            if t_lim[i_lim] >= t[i] {
+                self.add_censored_helper::<Erf>(
+                    &mut censored,
+                    idx_dt,
+                    m_lim[i_lim] - m[i],
+                    err,
+                    true,
+                );
+            } else {
+                self.add_censored_helper::<Erf>(
+                    &mut censored,
+                    idx_dt,
+                    m[i] - m_lim[i_lim],
+                    err,
+                    false,
+                );
+            }
+        });
+
+        a
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::ExactErf;
+
+    use approx::assert_abs_diff_eq;
+    use ndarray::{Array1, Axis};
+
+    #[test]
+    fn channels() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 32, 3.0_f64, 32);
+        let t = Array1::linspace(0.0, 100.0, 51);
+        let m = t.mapv(f64::sin);
+        let err2 = Array1::from_elem(51, 0.001);
+        let t_lim = Array1::linspace(0.5, 100.5, 51);
+        let m_lim = Array1::from_elem(51, 0.5);
+        let detections = (
+            t.as_slice().unwrap(),
+            m.as_slice().unwrap(),
+            err2.as_slice().unwrap(),
+        );
+        let upper_limits = (t_lim.as_slice().unwrap(), m_lim.as_slice().unwrap());
+
+        let merged = dmdt.gausses_with_upper_limits::<ExactErf>(
+            detections,
+            upper_limits,
+            UpperLimitChannel::Merged,
+        );
+        let separate = dmdt.gausses_with_upper_limits::<ExactErf>(
+            detections,
+            upper_limits,
+            UpperLimitChannel::Separate,
+        );
+
+        assert_eq!(
+            separate.index_axis(Axis(0), 0),
+            dmdt.gausses::<ExactErf>(detections.0, detections.1, detections.2)
+        );
+        assert_abs_diff_eq!(
+            merged.index_axis(Axis(0), 0).as_slice().unwrap(),
+            separate.sum_axis(Axis(0)).as_slice().unwrap(),
+            epsilon = 1e-12,
+        );
+    }
+
+    #[test]
+    fn one_sided() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 4, 2.0_f64, 40);
+        let detections = ([0.0].as_slice(), [1.0].as_slice(), [1e-4].as_slice());
+
+        // Upper limit after the detection: faded below 1.5, dm > 0.5
+        let later = dmdt.gausses_with_upper_limits::<ExactErf>(
+            detections,
+            ([10.0].as_slice(), [1.5].as_slice()),
+            UpperLimitChannel::Separate,
+        );
+        // Upper limit before the detection: brightened from below 1.5, dm < -0.5
+        let earlier = dmdt.gausses_with_upper_limits::<ExactErf>(
+            ([10.0].as_slice(), [1.0].as_slice(), [1e-4].as_slice()),
+            ([0.0].as_slice(), [1.5].as_slice()),
+            UpperLimitChannel::Separate,
+        );
+
+        let later_row = later.slice(s![1, 2, ..]);
+        let earlier_row = earlier.slice(s![1, 2, ..]);
+        assert_abs_diff_eq!(later_row.sum(), 1.0, epsilon = 1e-12);
+        // Allowed range is [0.5, 2.0), so each fully allowed cell has 0.1 / 1.5 of the mass
+        let centers = Array1::linspace(-1.95, 1.95, 40);
+        for (i, &center) in centers.iter().enumerate() {
+            if center < 0.2 {
+                assert_abs_diff_eq!(later_row[i], 0.0, epsilon = 1e-6);
+            }
+            if center > 0.8 {
+                assert_abs_diff_eq!(later_row[i], 0.1 / 1.5, epsilon = 1e-6);
+            }
+            assert_abs_diff_eq!(earlier_row[i], later_row[39 - i], epsilon = 1e-12);
+        }
+        assert!(later.slice(s![1, ..2, ..]).iter().all(|&x| x == 0.0));
+        assert!(later.slice(s![0, .., ..]).iter().all(|&x| x == 0.0));
+
+        // Bound outside of the dm grid
+        let outside = dmdt.gausses_with_upper_limits::<ExactErf>(
+            detections,
+            ([10.0].as_slice(), [4.0].as_slice()),
+            UpperLimitChannel::Merged,
+        );
+        assert!(outside.iter().all(|&x| x == 0.0));
+    }
+}
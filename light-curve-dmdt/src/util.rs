@@ -1,3 +1,6 @@
+use crate::Float;
+
+use conv::*;
 use itertools::Itertools;
 
 /// Checks if slice is sorted and have no duplicates
@@ -10,3 +13,27 @@ where
 {
     a.iter().tuple_windows().all(|(a, b)| a < b)
 }
+
+/// Sorts slice of non-NaN floats in ascending order
+pub fn sort_floats<T>(a: &mut [T])
+where
+    T: Float,
+{
+    a.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+}
+
+/// Quantile of the sorted slice using linear interpolation between the closest ranks
+///
+/// `q` must be in `[0, 1]` and `a` must be non-empty
+pub fn quantile_sorted<T>(a: &[T], q: T) -> T
+where
+    T: Float,
+{
+    let position = q * T::value_from(a.len() - 1).unwrap();
+    let idx: usize = position.approx_by::<RoundToZero>().unwrap();
+    if idx + 1 >= a.len() {
+        return a[a.len() - 1];
+    }
+    let alpha = position - T::value_from(idx).unwrap();
+    a[idx] * (T::one() - alpha) + a[idx + 1] * alpha
+}
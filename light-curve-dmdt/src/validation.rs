@@ -0,0 +1,236 @@
+use crate::{DmDt, ErfFloat, ErrorFunction, Float};
+
+use ndarray::{Array1, Array2};
+use std::borrow::Cow;
+use thiserror::Error;
+
+/// Error type for input validation of [DmDt] `try_*` methods
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DmDtInputError {
+    #[error("{name} has {len} values, but t has {t_len} values")]
+    LengthMismatch {
+        name: &'static str,
+        len: usize,
+        t_len: usize,
+    },
+    #[error("t is not ascending: value at index {0} is less than the previous one")]
+    UnsortedTime(usize),
+    #[error("{name} has non-finite value at index {index}")]
+    NonFinite { name: &'static str, index: usize },
+    #[error("err2 has negative value at index {0}")]
+    NegativeErr2(usize),
+}
+
+/// What to do if time values are not ascending, see [DmDt::try_points()]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnsortedPolicy {
+    /// Return [DmDtInputError::UnsortedTime]
+    #[default]
+    Error,
+    /// Sort observations by time, keeping the original order of observations having the same time
+    Sort,
+}
+
+type ValidatedInput<'a, T> = (Cow<'a, [T]>, Option<Cow<'a, [T]>>, Option<Cow<'a, [T]>>);
+
+/// Validates (t, m, err2) input and sorts it by time if required and allowed
+fn validate<'a, T>(
+    t: &'a [T],
+    m: Option<&'a [T]>,
+    err2: Option<&'a [T]>,
+    policy: UnsortedPolicy,
+) -> Result<ValidatedInput<'a, T>, DmDtInputError>
+where
+    T: Float,
+{
+    let columns = [("t", Some(t)), ("m", m), ("err2", err2)];
+    for (name, column) in columns {
+        let Some(column) = column else {
+            continue;
+        };
+        if column.len() != t.len() {
+            return Err(DmDtInputError::LengthMismatch {
+                name,
+                len: column.len(),
+                t_len: t.len(),
+            });
+        }
+        if let Some(index) = column.iter().position(|x| !x.is_finite()) {
+            return Err(DmDtInputError::NonFinite { name, index });
+        }
+    }
+    if let Some(index) = err2.and_then(|err2| err2.iter().position(|&x| x < T::zero())) {
+        return Err(DmDtInputError::NegativeErr2(index));
+    }
+
+    let Some(index) = t.windows(2).position(|w| w[1] < w[0]) else {
+        return Ok((
+            Cow::Borrowed(t),
+            m.map(Cow::Borrowed),
+            err2.map(Cow::Borrowed),
+        ));
+    };
+    match policy {
+        UnsortedPolicy::Error => Err(DmDtInputError::UnsortedTime(index + 1)),
+        UnsortedPolicy::Sort => {
+            let mut permutation: Vec<usize> = (0..t.len()).collect();
+            // All values are finite, so partial_cmp cannot fail
+            permutation.sort_by(|&i, &j| t[i].partial_cmp(&t[j]).unwrap());
+            let permute = |a: &[T]| -> Cow<'a, [T]> {
+                Cow::Owned(permutation.iter().map(|&i| a[i]).collect())
+            };
+            Ok((permute(t), m.map(permute), err2.map(permute)))
+        }
+    }
+}
+
+impl<T> DmDt<T>
+where
+    T: Float,
+{
+    /// Fallible version of [DmDt::points()]
+    ///
+    /// Returns an error if slices have different lengths or non-finite values. Non-ascending `t`
+    /// is handled according to `policy`.
+    pub fn try_points(
+        &self,
+        t: &[T],
+        m: &[T],
+        policy: UnsortedPolicy,
+    ) -> Result<Array2<u64>, DmDtInputError> {
+        let (t, m, _) = validate(t, Some(m), None, policy)?;
+        Ok(self.points(&t, &m.unwrap()))
+    }
+
+    /// Fallible version of [DmDt::gausses()]
+    ///
+    /// Returns an error if slices have different lengths, non-finite values or `err2` has negative
+    /// values. Non-ascending `t` is handled according to `policy`.
+    pub fn try_gausses<Erf>(
+        &self,
+        t: &[T],
+        m: &[T],
+        err2: &[T],
+        policy: UnsortedPolicy,
+    ) -> Result<Array2<T>, DmDtInputError>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        let (t, m, err2) = validate(t, Some(m), Some(err2), policy)?;
+        Ok(self.gausses::<Erf>(&t, &m.unwrap(), &err2.unwrap()))
+    }
+
+    /// Fallible version of [DmDt::dt_points()]
+    ///
+    /// Returns an error if `t` has non-finite values. Non-ascending `t` is handled according to
+    /// `policy`.
+    pub fn try_dt_points(
+        &self,
+        t: &[T],
+        policy: UnsortedPolicy,
+    ) -> Result<Array1<u64>, DmDtInputError> {
+        let (t, _, _) = validate(t, None, None, policy)?;
+        Ok(self.dt_points(&t))
+    }
+
+    /// Fallible version of [DmDt::cond_prob()]
+    ///
+    /// Returns an error if slices have different lengths, non-finite values or `err2` has negative
+    /// values. Non-ascending `t` is handled according to `policy`.
+    pub fn try_cond_prob<Erf>(
+        &self,
+        t: &[T],
+        m: &[T],
+        err2: &[T],
+        policy: UnsortedPolicy,
+    ) -> Result<Array2<T>, DmDtInputError>
+    where
+        T: ErfFloat,
+        Erf: ErrorFunction<T>,
+    {
+        let (t, m, err2) = validate(t, Some(m), Some(err2), policy)?;
+        Ok(self.cond_prob::<Erf>(&t, &m.unwrap(), &err2.unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::erf::ExactErf;
+
+    #[test]
+    fn errors() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 32, 3.0_f64, 32);
+        let t = [0.0, 1.0, 3.0, 2.0];
+        let m = [1.0, 2.0, 1.5, 1.0];
+        let err2 = [0.1, 0.1, -0.1, 0.1];
+
+        assert_eq!(
+            dmdt.try_points(&t, &m[..3], UnsortedPolicy::Sort),
+            Err(DmDtInputError::LengthMismatch {
+                name: "m",
+                len: 3,
+                t_len: 4
+            })
+        );
+        assert_eq!(
+            dmdt.try_points(&t, &m, UnsortedPolicy::Error),
+            Err(DmDtInputError::UnsortedTime(3))
+        );
+        assert_eq!(
+            dmdt.try_dt_points(&[0.0, f64::NAN], UnsortedPolicy::Sort),
+            Err(DmDtInputError::NonFinite {
+                name: "t",
+                index: 1
+            })
+        );
+        assert_eq!(
+            dmdt.try_gausses::<ExactErf>(&t, &m, &err2, UnsortedPolicy::Sort),
+            Err(DmDtInputError::NegativeErr2(2))
+        );
+    }
+
+    #[test]
+    fn sort() {
+        let dmdt = DmDt::from_lgdt_dm_limits(0.0_f64, 2.0_f64, 32, 3.0_f64, 32);
+        let t = Array1::linspace(0.0, 100.0, 101);
+        let m = t.mapv(f64::sin);
+        let err2 = Array1::from_elem(101, 0.001);
+        let permutation: Vec<_> = (0..101).map(|i| (i * 37) % 101).collect();
+        let shuffled = |a: &Array1<f64>| permutation.iter().map(|&i| a[i]).collect::<Vec<_>>();
+        let (t_shuffled, m_shuffled, err2_shuffled) = (shuffled(&t), shuffled(&m), shuffled(&err2));
+        let (t, m, err2) = (
+            t.as_slice().unwrap(),
+            m.as_slice().unwrap(),
+            err2.as_slice().unwrap(),
+        );
+
+        assert_eq!(
+            dmdt.try_points(&t_shuffled, &m_shuffled, UnsortedPolicy::Sort)
+                .unwrap(),
+            dmdt.points(t, m),
+        );
+        assert_eq!(
+            dmdt.try_dt_points(&t_shuffled, UnsortedPolicy::Sort)
+                .unwrap(),
+            dmdt.dt_points(t),
+        );
+        assert_eq!(
+            dmdt.try_cond_prob::<ExactErf>(
+                &t_shuffled,
+                &m_shuffled,
+                &err2_shuffled,
+                UnsortedPolicy::Sort
+            )
+            .unwrap(),
+            dmdt.cond_prob::<ExactErf>(t, m, err2),
+        );
+        assert_eq!(
+            dmdt.try_gausses::<ExactErf>(t, m, err2, UnsortedPolicy::Error)
+                .unwrap(),
+            dmdt.gausses::<ExactErf>(t, m, err2),
+        );
+    }
+}